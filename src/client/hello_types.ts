@@ -0,0 +1,351 @@
+// Generated by `npm run codegen` from src/program-rust/client/src/codegen.rs,
+// do not edit.
+//
+// Byte layouts of the hello world program's instructions and greeting
+// accounts. Encoders throw RangeError on values that don't fit their field,
+// decoders on data that is short, has trailing bytes or has an unknown tag.
+
+import {Buffer} from 'buffer';
+
+/**
+ * Size in bytes of a greeting account
+ */
+export const GREETING_ACCOUNT_SIZE = 4;
+
+/**
+ * The state of a greeting account
+ */
+export interface GreetingAccount {
+  counter: number;
+}
+
+export function encodeGreetingAccount(account: GreetingAccount): Buffer {
+  return Buffer.concat([u32(account.counter)]);
+}
+
+export function decodeGreetingAccount(data: Buffer): GreetingAccount {
+  const reader = new Reader(data);
+  const account = {counter: reader.u32()};
+  reader.end();
+  return account;
+}
+
+/**
+ * An instruction of the hello world program, `kind` is its HelloInstruction
+ * variant
+ */
+export type HelloInstruction =
+  | {kind: 'Increment'}
+  | {kind: 'Decrement'}
+  | {kind: 'Set'; value: number}
+  | {kind: 'GetVersion'}
+  | {kind: 'Batch'; ops: HelloInstruction[]}
+  | {kind: 'MirrorIncrement'}
+  | {kind: 'CopyFrom'}
+  | {kind: 'Upvote'}
+  | {kind: 'Downvote'}
+  | {kind: 'CreatePoll'; labels: string[]}
+  | {kind: 'Vote'; index: number}
+  | {kind: 'ClosePoll'}
+  | {kind: 'Attest'; hash: Buffer}
+  | {kind: 'SetUri'; uri: string}
+  | {kind: 'SetLanguage'; language: Buffer}
+  | {kind: 'Idempotent'; opId: Buffer; op: HelloInstruction}
+  | {kind: 'Deposit'; amount: number; target: number}
+  | {kind: 'Withdraw'}
+  | {kind: 'ClaimBadge'; milestone: number}
+  | {kind: 'CheckIn'}
+  | {kind: 'CheckUpgradeAuthority'; strict: boolean}
+  | {kind: 'ProposeSet'; value: number}
+  | {kind: 'ConfirmSet'}
+  | {kind: 'SetAllowlist'; root: Buffer}
+  | {kind: 'AllowlistedIncrement'; proof: Buffer[]}
+  | {kind: 'SignedIncrement'}
+  | {kind: 'GetMultiple'}
+  | {kind: 'CancelSet'};
+
+function u8(value: number): Buffer {
+  const data = Buffer.alloc(1);
+  data.writeUInt8(value, 0);
+  return data;
+}
+
+function u32(value: number): Buffer {
+  const data = Buffer.alloc(4);
+  data.writeUInt32LE(value, 0);
+  return data;
+}
+
+function u64(value: number): Buffer {
+  if (!Number.isSafeInteger(value) || value < 0) {
+    throw new RangeError(`${value} is not a u64 below 2^53`);
+  }
+  const data = Buffer.alloc(8);
+  data.writeUInt32LE(value % 0x100000000, 0);
+  data.writeUInt32LE(Math.floor(value / 0x100000000), 4);
+  return data;
+}
+
+function bytes(value: Buffer, len: number): Buffer {
+  if (value.length !== len) {
+    throw new RangeError(`expected ${len} bytes, got ${value.length}`);
+  }
+  return value;
+}
+
+// a u8 length followed by `data`
+function prefixed(data: Buffer): Buffer {
+  return Buffer.concat([u8(data.length), data]);
+}
+
+function string(value: string): Buffer {
+  return prefixed(Buffer.from(value, 'utf8'));
+}
+
+function strings(values: string[]): Buffer {
+  return Buffer.concat([
+    u8(values.length),
+    ...values.map(value => string(value)),
+  ]);
+}
+
+function hashes(values: Buffer[]): Buffer {
+  return Buffer.concat([
+    u8(values.length),
+    ...values.map(value => bytes(value, 32)),
+  ]);
+}
+
+function ops(values: HelloInstruction[]): Buffer {
+  return Buffer.concat([
+    u8(values.length),
+    ...values.map(value => prefixed(encodeHelloInstruction(value))),
+  ]);
+}
+
+class Reader {
+  private offset = 0;
+
+  constructor(private readonly data: Buffer) {}
+
+  // throws unless all of the data has been read
+  end(): void {
+    if (this.offset !== this.data.length) {
+      throw new RangeError('data has trailing bytes');
+    }
+  }
+
+  u8(): number {
+    return this.take(1).readUInt8(0);
+  }
+
+  bool(): boolean {
+    const value = this.u8();
+    if (value > 1) {
+      throw new RangeError(`${value} is not a bool`);
+    }
+    return value === 1;
+  }
+
+  u32(): number {
+    return this.take(4).readUInt32LE(0);
+  }
+
+  u64(): number {
+    const data = this.take(8);
+    const value = data.readUInt32LE(4) * 0x100000000 + data.readUInt32LE(0);
+    if (!Number.isSafeInteger(value)) {
+      throw new RangeError('u64 is above 2^53');
+    }
+    return value;
+  }
+
+  bytes(len: number): Buffer {
+    return this.take(len);
+  }
+
+  string(): string {
+    return this.take(this.u8()).toString('utf8');
+  }
+
+  strings(): string[] {
+    return this.list(() => this.string());
+  }
+
+  hashes(): Buffer[] {
+    return this.list(() => this.bytes(32));
+  }
+
+  ops(): HelloInstruction[] {
+    return this.list(() => decodeHelloInstruction(this.take(this.u8())));
+  }
+
+  private take(len: number): Buffer {
+    if (this.offset + len > this.data.length) {
+      throw new RangeError('data is too short');
+    }
+    const value = this.data.slice(this.offset, this.offset + len);
+    this.offset += len;
+    return value;
+  }
+
+  // a u8 count followed by that many items
+  private list<T>(item: () => T): T[] {
+    const count = this.u8();
+    const items: T[] = [];
+    for (let i = 0; i < count; i++) {
+      items.push(item());
+    }
+    return items;
+  }
+}
+
+/**
+ * Instruction data for `instruction`
+ */
+export function encodeHelloInstruction(instruction: HelloInstruction): Buffer {
+  switch (instruction.kind) {
+    case 'Increment':
+      return u8(0);
+    case 'Decrement':
+      return u8(1);
+    case 'Set':
+      return Buffer.concat([u8(2), u32(instruction.value)]);
+    case 'GetVersion':
+      return u8(3);
+    case 'Batch':
+      return Buffer.concat([u8(4), ops(instruction.ops)]);
+    case 'MirrorIncrement':
+      return u8(5);
+    case 'CopyFrom':
+      return u8(6);
+    case 'Upvote':
+      return u8(7);
+    case 'Downvote':
+      return u8(8);
+    case 'CreatePoll':
+      return Buffer.concat([u8(9), strings(instruction.labels)]);
+    case 'Vote':
+      return Buffer.concat([u8(10), u8(instruction.index)]);
+    case 'ClosePoll':
+      return u8(11);
+    case 'Attest':
+      return Buffer.concat([u8(12), bytes(instruction.hash, 32)]);
+    case 'SetUri':
+      return Buffer.concat([u8(13), string(instruction.uri)]);
+    case 'SetLanguage':
+      return Buffer.concat([u8(14), bytes(instruction.language, 2)]);
+    case 'Idempotent':
+      return Buffer.concat([
+        u8(15),
+        bytes(instruction.opId, 16),
+        encodeHelloInstruction(instruction.op),
+      ]);
+    case 'Deposit':
+      return Buffer.concat([
+        u8(16),
+        u64(instruction.amount),
+        u32(instruction.target),
+      ]);
+    case 'Withdraw':
+      return u8(17);
+    case 'ClaimBadge':
+      return Buffer.concat([u8(18), u32(instruction.milestone)]);
+    case 'CheckIn':
+      return u8(19);
+    case 'CheckUpgradeAuthority':
+      return Buffer.concat([u8(20), u8(instruction.strict ? 1 : 0)]);
+    case 'ProposeSet':
+      return Buffer.concat([u8(21), u32(instruction.value)]);
+    case 'ConfirmSet':
+      return u8(22);
+    case 'SetAllowlist':
+      return Buffer.concat([u8(23), bytes(instruction.root, 32)]);
+    case 'AllowlistedIncrement':
+      return Buffer.concat([u8(24), hashes(instruction.proof)]);
+    case 'SignedIncrement':
+      return u8(25);
+    case 'GetMultiple':
+      return u8(26);
+    case 'CancelSet':
+      return u8(27);
+  }
+}
+
+/**
+ * The instruction encoded in `data`
+ */
+export function decodeHelloInstruction(data: Buffer): HelloInstruction {
+  const reader = new Reader(data);
+  const instruction = readInstruction(reader);
+  reader.end();
+  return instruction;
+}
+
+function readInstruction(reader: Reader): HelloInstruction {
+  const tag = reader.u8();
+  switch (tag) {
+    case 0:
+      return {kind: 'Increment'};
+    case 1:
+      return {kind: 'Decrement'};
+    case 2:
+      return {kind: 'Set', value: reader.u32()};
+    case 3:
+      return {kind: 'GetVersion'};
+    case 4:
+      return {kind: 'Batch', ops: reader.ops()};
+    case 5:
+      return {kind: 'MirrorIncrement'};
+    case 6:
+      return {kind: 'CopyFrom'};
+    case 7:
+      return {kind: 'Upvote'};
+    case 8:
+      return {kind: 'Downvote'};
+    case 9:
+      return {kind: 'CreatePoll', labels: reader.strings()};
+    case 10:
+      return {kind: 'Vote', index: reader.u8()};
+    case 11:
+      return {kind: 'ClosePoll'};
+    case 12:
+      return {kind: 'Attest', hash: reader.bytes(32)};
+    case 13:
+      return {kind: 'SetUri', uri: reader.string()};
+    case 14:
+      return {kind: 'SetLanguage', language: reader.bytes(2)};
+    case 15:
+      return {
+        kind: 'Idempotent',
+        opId: reader.bytes(16),
+        op: readInstruction(reader),
+      };
+    case 16:
+      return {kind: 'Deposit', amount: reader.u64(), target: reader.u32()};
+    case 17:
+      return {kind: 'Withdraw'};
+    case 18:
+      return {kind: 'ClaimBadge', milestone: reader.u32()};
+    case 19:
+      return {kind: 'CheckIn'};
+    case 20:
+      return {kind: 'CheckUpgradeAuthority', strict: reader.bool()};
+    case 21:
+      return {kind: 'ProposeSet', value: reader.u32()};
+    case 22:
+      return {kind: 'ConfirmSet'};
+    case 23:
+      return {kind: 'SetAllowlist', root: reader.bytes(32)};
+    case 24:
+      return {kind: 'AllowlistedIncrement', proof: reader.hashes()};
+    case 25:
+      return {kind: 'SignedIncrement'};
+    case 26:
+      return {kind: 'GetMultiple'};
+    case 27:
+      return {kind: 'CancelSet'};
+    default:
+      throw new RangeError(`unknown instruction tag ${tag}`);
+  }
+}
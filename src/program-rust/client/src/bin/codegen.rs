@@ -0,0 +1,8 @@
+//src/program-rust/client/src/bin/codegen.rs
+// Prints src/client/hello_types.ts, see src/codegen.rs
+//
+//   npm run codegen
+
+fn main() {
+    print!("{}", hello_client::codegen::typescript());
+}
@@ -0,0 +1,149 @@
+//src/program-rust/client/src/bin/profile.rs
+// Compute units each instruction uses, across input sizes
+//
+//   cargo build-bpf
+//   BPF_OUT_DIR=target/deploy cargo run -p hello-client --features profile --bin profile
+//
+// program-test doesn't report what a transaction consumed, so every case
+// is measured by searching for the smallest set_bpf_compute_max_units()
+// budget it succeeds with, on a fresh bank each try. That takes a while
+// but gives the exact figure, CPIs included. Only the BPF build is
+// metered, so the program has to be built with `cargo build-bpf` first.
+//
+// The table is meant for picking compute budgets and for spotting costs
+// that grow faster than their input: compare the per item column across the
+// rows of one instruction.
+
+use hello_client::instruction::{self, HelloInstruction, MAX_BATCH_LEN};
+use hello_client::state::{GreetingAccount, MAX_URI_LEN};
+use solana_program_test::{find_file, tokio, ProgramTest};
+use solana_sdk::{
+    account::Account, instruction::Instruction, pubkey::Pubkey, signature::Signer,
+    transaction::Transaction,
+};
+use std::process;
+
+// What a transaction may use at most
+const MAX_UNITS: u64 = 200_000;
+
+// One row of the table. `build` makes the instruction from the program
+// id, a greeting account and the payer, who signs.
+struct Case {
+    instruction: &'static str,
+    size: usize,
+    build: Box<dyn Fn(&Pubkey, &Pubkey, &Pubkey) -> Instruction>,
+}
+
+fn cases() -> Vec<Case> {
+    let mut cases = vec![
+        Case {
+            instruction: "increment",
+            size: 0,
+            build: Box::new(|program_id, greeting, _| instruction::increment(program_id, greeting)),
+        },
+        Case {
+            instruction: "decrement",
+            size: 0,
+            build: Box::new(|program_id, greeting, _| instruction::decrement(program_id, greeting)),
+        },
+        Case {
+            instruction: "set",
+            size: 0,
+            build: Box::new(|program_id, greeting, _| instruction::set(program_id, greeting, 7)),
+        },
+        Case {
+            instruction: "get_version",
+            size: 0,
+            build: Box::new(|program_id, _, _| instruction::get_version(program_id)),
+        },
+    ];
+    for &len in &[1, 2, 4, 8, MAX_BATCH_LEN] {
+        cases.push(Case {
+            instruction: "batch",
+            size: len,
+            build: Box::new(move |program_id, greeting, _| {
+                let ops = (0..len).map(|_| HelloInstruction::Increment).collect();
+                instruction::batch(program_id, greeting, ops)
+            }),
+        });
+    }
+    for &len in &[0, 50, 100, MAX_URI_LEN] {
+        cases.push(Case {
+            instruction: "set_uri",
+            size: len,
+            build: Box::new(move |program_id, greeting, payer| {
+                instruction::set_uri(program_id, greeting, payer, "x".repeat(len))
+            }),
+        });
+    }
+    cases
+}
+
+// Whether `case` succeeds within `units`
+async fn succeeds_within(case: &Case, units: u64) -> bool {
+    let program_id = Pubkey::new_unique();
+    let greeting = Pubkey::new_unique();
+    let mut program_test = ProgramTest::new("helloworld", program_id, None);
+    program_test.prefer_bpf(true);
+    program_test.set_bpf_compute_max_units(units);
+    program_test.add_account(
+        greeting,
+        Account {
+            lamports: 1_000_000,
+            data: vec![0; GreetingAccount::LEN],
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let ix = (case.build)(&program_id, &greeting, &payer.pubkey());
+    let mut transaction = Transaction::new_with_payer(&[ix], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.is_ok()
+}
+
+// The smallest budget `case` succeeds with, None if it fails even with
+// MAX_UNITS
+async fn units_used(case: &Case) -> Option<u64> {
+    if !succeeds_within(case, MAX_UNITS).await {
+        return None;
+    }
+    let (mut low, mut high) = (0, MAX_UNITS);
+    // fails with `low`, succeeds with `high`
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if succeeds_within(case, mid).await {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+    Some(high)
+}
+
+#[tokio::main]
+async fn main() {
+    if find_file("helloworld.so").is_none() {
+        eprintln!("helloworld.so not found, build it with `cargo build-bpf` and set BPF_OUT_DIR");
+        process::exit(2);
+    }
+
+    println!(
+        "{:<12} {:>6} {:>10} {:>10}",
+        "instruction", "size", "units", "per item"
+    );
+    for case in cases() {
+        let (units, per_item) = match units_used(&case).await {
+            Some(units) if case.size > 0 => {
+                (units.to_string(), (units / case.size as u64).to_string())
+            }
+            Some(units) => (units.to_string(), "-".to_string()),
+            None => (format!(">{}", MAX_UNITS), "-".to_string()),
+        };
+        println!(
+            "{:<12} {:>6} {:>10} {:>10}",
+            case.instruction, case.size, units, per_item
+        );
+    }
+}
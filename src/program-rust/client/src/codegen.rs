@@ -0,0 +1,527 @@
+//src/program-rust/client/src/codegen.rs
+// TypeScript encoders and decoders for the web client
+//
+// typescript() writes src/client/hello_types.ts: a HelloInstruction union
+// with encodeHelloInstruction() and decodeHelloInstruction(), and the
+// GreetingAccount interface with its own pair. The layouts are described
+// once, in INSTRUCTIONS and GREETING_ACCOUNT below, and
+// test_layouts_match_pack checks every entry against pack() and unpack(),
+// so a change to an instruction fails the tests until the table follows.
+// test_typescript_is_current then fails until the file is regenerated:
+//
+//   npm run codegen
+//
+// ts-rs and BorshSchema only know Borsh, and HelloInstruction isn't Borsh
+// (its vectors and strings have u8 lengths), so neither fits here.
+
+use std::fmt::Write;
+
+/// How a field is encoded, the way unpack() reads it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Field {
+    U8,
+    /// A u8 that is 0 or 1
+    Bool,
+    /// Little endian, as are U64s
+    U32,
+    /// A number in TypeScript, so at most 2^53 - 1
+    U64,
+    /// Fixed length bytes
+    Bytes(usize),
+    /// A u8 length followed by UTF-8 bytes
+    String,
+    /// A u8 count followed by that many Strings
+    Strings,
+    /// A u8 count followed by that many 32 byte hashes
+    Hashes,
+    /// A u8 count followed by that many instructions, each with a u8 length
+    Ops,
+    /// An instruction taking up the rest of the data
+    Op,
+}
+
+impl Field {
+    // Size in bytes, None if it depends on the value
+    fn size(self) -> Option<usize> {
+        match self {
+            Field::U8 | Field::Bool => Some(1),
+            Field::U32 => Some(4),
+            Field::U64 => Some(8),
+            Field::Bytes(len) => Some(len),
+            _ => None,
+        }
+    }
+
+    fn ts_type(self) -> &'static str {
+        match self {
+            Field::U8 | Field::U32 | Field::U64 => "number",
+            Field::Bool => "boolean",
+            Field::Bytes(_) => "Buffer",
+            Field::String => "string",
+            Field::Strings => "string[]",
+            Field::Hashes => "Buffer[]",
+            Field::Ops => "HelloInstruction[]",
+            Field::Op => "HelloInstruction",
+        }
+    }
+
+    // Expression encoding `value` into a Buffer
+    fn encode(self, value: &str) -> String {
+        match self {
+            Field::U8 => format!("u8({})", value),
+            Field::Bool => format!("u8({} ? 1 : 0)", value),
+            Field::U32 => format!("u32({})", value),
+            Field::U64 => format!("u64({})", value),
+            Field::Bytes(len) => format!("bytes({}, {})", value, len),
+            Field::String => format!("string({})", value),
+            Field::Strings => format!("strings({})", value),
+            Field::Hashes => format!("hashes({})", value),
+            Field::Ops => format!("ops({})", value),
+            Field::Op => format!("encodeHelloInstruction({})", value),
+        }
+    }
+
+    // Expression reading the field from `reader`
+    fn decode(self) -> String {
+        match self {
+            Field::U8 => "reader.u8()".into(),
+            Field::Bool => "reader.bool()".into(),
+            Field::U32 => "reader.u32()".into(),
+            Field::U64 => "reader.u64()".into(),
+            Field::Bytes(len) => format!("reader.bytes({})", len),
+            Field::String => "reader.string()".into(),
+            Field::Strings => "reader.strings()".into(),
+            Field::Hashes => "reader.hashes()".into(),
+            Field::Ops => "reader.ops()".into(),
+            Field::Op => "readInstruction(reader)".into(),
+        }
+    }
+}
+
+/// Named fields in the order they are encoded. The names are the
+/// TypeScript ones.
+pub type Fields = &'static [(&'static str, Field)];
+
+/// HelloInstruction variant, tag and fields, in the order pack() writes
+/// them
+pub const INSTRUCTIONS: &[(&str, u8, Fields)] = &[
+    ("Increment", 0, &[]),
+    ("Decrement", 1, &[]),
+    ("Set", 2, &[("value", Field::U32)]),
+    ("GetVersion", 3, &[]),
+    ("Batch", 4, &[("ops", Field::Ops)]),
+    ("MirrorIncrement", 5, &[]),
+    ("CopyFrom", 6, &[]),
+    ("Upvote", 7, &[]),
+    ("Downvote", 8, &[]),
+    ("CreatePoll", 9, &[("labels", Field::Strings)]),
+    ("Vote", 10, &[("index", Field::U8)]),
+    ("ClosePoll", 11, &[]),
+    ("Attest", 12, &[("hash", Field::Bytes(32))]),
+    ("SetUri", 13, &[("uri", Field::String)]),
+    ("SetLanguage", 14, &[("language", Field::Bytes(2))]),
+    (
+        "Idempotent",
+        15,
+        &[("opId", Field::Bytes(16)), ("op", Field::Op)],
+    ),
+    (
+        "Deposit",
+        16,
+        &[("amount", Field::U64), ("target", Field::U32)],
+    ),
+    ("Withdraw", 17, &[]),
+    ("ClaimBadge", 18, &[("milestone", Field::U32)]),
+    ("CheckIn", 19, &[]),
+    ("CheckUpgradeAuthority", 20, &[("strict", Field::Bool)]),
+    ("ProposeSet", 21, &[("value", Field::U32)]),
+    ("ConfirmSet", 22, &[]),
+    ("SetAllowlist", 23, &[("root", Field::Bytes(32))]),
+    ("AllowlistedIncrement", 24, &[("proof", Field::Hashes)]),
+    ("SignedIncrement", 25, &[]),
+    ("GetMultiple", 26, &[]),
+    ("CancelSet", 27, &[]),
+];
+
+/// Fields of GreetingAccount, in the order Borsh writes them
+pub const GREETING_ACCOUNT: Fields = &[("counter", Field::U32)];
+
+// prettier's line width, the generated file is kept formatted
+const WIDTH: usize = 80;
+
+// `open`, then `items` separated by commas, then `close`, on one line at
+// `indent` if that fits, otherwise one item per line the way prettier
+// breaks it
+fn wrap(indent: usize, open: &str, items: &[String], close: &str) -> String {
+    let line = format!(
+        "{}{}{}{}",
+        " ".repeat(indent),
+        open,
+        items.join(", "),
+        close
+    );
+    if line.len() <= WIDTH {
+        return line + "\n";
+    }
+    let mut out = format!("{}{}\n", " ".repeat(indent), open.trim_end());
+    for item in items {
+        let _ = writeln!(out, "{}{},", " ".repeat(indent + 2), item);
+    }
+    let _ = writeln!(out, "{}{}", " ".repeat(indent), close.trim_start());
+    out
+}
+
+const HEADER: &str = "\
+// Generated by `npm run codegen` from src/program-rust/client/src/codegen.rs,
+// do not edit.
+//
+// Byte layouts of the hello world program's instructions and greeting
+// accounts. Encoders throw RangeError on values that don't fit their field,
+// decoders on data that is short, has trailing bytes or has an unknown tag.
+
+import {Buffer} from 'buffer';
+";
+
+// Field encoders and the Reader the decoders share
+const HELPERS: &str = "
+function u8(value: number): Buffer {
+  const data = Buffer.alloc(1);
+  data.writeUInt8(value, 0);
+  return data;
+}
+
+function u32(value: number): Buffer {
+  const data = Buffer.alloc(4);
+  data.writeUInt32LE(value, 0);
+  return data;
+}
+
+function u64(value: number): Buffer {
+  if (!Number.isSafeInteger(value) || value < 0) {
+    throw new RangeError(`${value} is not a u64 below 2^53`);
+  }
+  const data = Buffer.alloc(8);
+  data.writeUInt32LE(value % 0x100000000, 0);
+  data.writeUInt32LE(Math.floor(value / 0x100000000), 4);
+  return data;
+}
+
+function bytes(value: Buffer, len: number): Buffer {
+  if (value.length !== len) {
+    throw new RangeError(`expected ${len} bytes, got ${value.length}`);
+  }
+  return value;
+}
+
+// a u8 length followed by `data`
+function prefixed(data: Buffer): Buffer {
+  return Buffer.concat([u8(data.length), data]);
+}
+
+function string(value: string): Buffer {
+  return prefixed(Buffer.from(value, 'utf8'));
+}
+
+function strings(values: string[]): Buffer {
+  return Buffer.concat([
+    u8(values.length),
+    ...values.map(value => string(value)),
+  ]);
+}
+
+function hashes(values: Buffer[]): Buffer {
+  return Buffer.concat([
+    u8(values.length),
+    ...values.map(value => bytes(value, 32)),
+  ]);
+}
+
+function ops(values: HelloInstruction[]): Buffer {
+  return Buffer.concat([
+    u8(values.length),
+    ...values.map(value => prefixed(encodeHelloInstruction(value))),
+  ]);
+}
+
+class Reader {
+  private offset = 0;
+
+  constructor(private readonly data: Buffer) {}
+
+  // throws unless all of the data has been read
+  end(): void {
+    if (this.offset !== this.data.length) {
+      throw new RangeError('data has trailing bytes');
+    }
+  }
+
+  u8(): number {
+    return this.take(1).readUInt8(0);
+  }
+
+  bool(): boolean {
+    const value = this.u8();
+    if (value > 1) {
+      throw new RangeError(`${value} is not a bool`);
+    }
+    return value === 1;
+  }
+
+  u32(): number {
+    return this.take(4).readUInt32LE(0);
+  }
+
+  u64(): number {
+    const data = this.take(8);
+    const value = data.readUInt32LE(4) * 0x100000000 + data.readUInt32LE(0);
+    if (!Number.isSafeInteger(value)) {
+      throw new RangeError('u64 is above 2^53');
+    }
+    return value;
+  }
+
+  bytes(len: number): Buffer {
+    return this.take(len);
+  }
+
+  string(): string {
+    return this.take(this.u8()).toString('utf8');
+  }
+
+  strings(): string[] {
+    return this.list(() => this.string());
+  }
+
+  hashes(): Buffer[] {
+    return this.list(() => this.bytes(32));
+  }
+
+  ops(): HelloInstruction[] {
+    return this.list(() => decodeHelloInstruction(this.take(this.u8())));
+  }
+
+  private take(len: number): Buffer {
+    if (this.offset + len > this.data.length) {
+      throw new RangeError('data is too short');
+    }
+    const value = this.data.slice(this.offset, this.offset + len);
+    this.offset += len;
+    return value;
+  }
+
+  // a u8 count followed by that many items
+  private list<T>(item: () => T): T[] {
+    const count = this.u8();
+    const items: T[] = [];
+    for (let i = 0; i < count; i++) {
+      items.push(item());
+    }
+    return items;
+  }
+}
+";
+
+fn greeting_account(out: &mut String) {
+    let size: usize = GREETING_ACCOUNT
+        .iter()
+        .map(|(_, field)| {
+            field
+                .size()
+                .expect("greeting account fields have a fixed size")
+        })
+        .sum();
+    let _ = write!(
+        out,
+        "
+/**
+ * Size in bytes of a greeting account
+ */
+export const GREETING_ACCOUNT_SIZE = {};
+
+/**
+ * The state of a greeting account
+ */
+export interface GreetingAccount {{
+",
+        size
+    );
+    for (name, field) in GREETING_ACCOUNT {
+        let _ = writeln!(out, "  {}: {};", name, field.ts_type());
+    }
+    out.push_str(
+        "}\n\nexport function encodeGreetingAccount(account: GreetingAccount): Buffer {\n",
+    );
+    let fields: Vec<_> = GREETING_ACCOUNT
+        .iter()
+        .map(|(name, field)| field.encode(&format!("account.{}", name)))
+        .collect();
+    out.push_str(&wrap(2, "return Buffer.concat([", &fields, "]);"));
+    out.push_str(
+        "}
+
+export function decodeGreetingAccount(data: Buffer): GreetingAccount {
+  const reader = new Reader(data);
+",
+    );
+    let fields: Vec<_> = GREETING_ACCOUNT
+        .iter()
+        .map(|(name, field)| format!("{}: {}", name, field.decode()))
+        .collect();
+    out.push_str(&wrap(2, "const account = {", &fields, "};"));
+    out.push_str("  reader.end();\n  return account;\n}\n");
+}
+
+fn instruction_type(out: &mut String) {
+    out.push_str(
+        "
+/**
+ * An instruction of the hello world program, `kind` is its HelloInstruction
+ * variant
+ */
+export type HelloInstruction =
+",
+    );
+    for (i, (name, _, fields)) in INSTRUCTIONS.iter().enumerate() {
+        let mut members = vec![format!("kind: '{}'", name)];
+        members.extend(
+            fields
+                .iter()
+                .map(|(field_name, field)| format!("{}: {}", field_name, field.ts_type())),
+        );
+        let end = if i + 1 == INSTRUCTIONS.len() { ";" } else { "" };
+        let _ = writeln!(out, "  | {{{}}}{}", members.join("; "), end);
+    }
+}
+
+fn instruction_codecs(out: &mut String) {
+    out.push_str(
+        "
+/**
+ * Instruction data for `instruction`
+ */
+export function encodeHelloInstruction(instruction: HelloInstruction): Buffer {
+  switch (instruction.kind) {
+",
+    );
+    for (name, tag, fields) in INSTRUCTIONS {
+        let _ = writeln!(out, "    case '{}':", name);
+        if fields.is_empty() {
+            let _ = writeln!(out, "      return u8({});", tag);
+            continue;
+        }
+        let mut parts = vec![format!("u8({})", tag)];
+        parts.extend(
+            fields
+                .iter()
+                .map(|(field_name, field)| field.encode(&format!("instruction.{}", field_name))),
+        );
+        out.push_str(&wrap(6, "return Buffer.concat([", &parts, "]);"));
+    }
+    out.push_str(
+        "  }
+}
+
+/**
+ * The instruction encoded in `data`
+ */
+export function decodeHelloInstruction(data: Buffer): HelloInstruction {
+  const reader = new Reader(data);
+  const instruction = readInstruction(reader);
+  reader.end();
+  return instruction;
+}
+
+function readInstruction(reader: Reader): HelloInstruction {
+  const tag = reader.u8();
+  switch (tag) {
+",
+    );
+    for (name, tag, fields) in INSTRUCTIONS {
+        let _ = writeln!(out, "    case {}:", tag);
+        let mut members = vec![format!("kind: '{}'", name)];
+        members.extend(
+            fields
+                .iter()
+                .map(|(field_name, field)| format!("{}: {}", field_name, field.decode())),
+        );
+        out.push_str(&wrap(6, "return {", &members, "};"));
+    }
+    out.push_str(
+        "    default:
+      throw new RangeError(`unknown instruction tag ${tag}`);
+  }
+}
+",
+    );
+}
+
+/// The contents of src/client/hello_types.ts
+pub fn typescript() -> String {
+    let mut out = HEADER.to_string();
+    greeting_account(&mut out);
+    instruction_type(&mut out);
+    out.push_str(HELPERS);
+    instruction_codecs(&mut out);
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use borsh::BorshDeserialize;
+    use hello_types::instruction::HelloInstruction;
+    use hello_types::state::GreetingAccount;
+    use std::{fs, path::Path};
+
+    // Bytes of a value unpack() accepts for `field`
+    fn sample(field: Field) -> Vec<u8> {
+        match field {
+            Field::U8 | Field::Bool => vec![1],
+            // a milestone, so ClaimBadge takes it too
+            Field::U32 => 100u32.to_le_bytes().to_vec(),
+            Field::U64 => 9u64.to_le_bytes().to_vec(),
+            Field::Bytes(len) => vec![3; len],
+            Field::String => vec![2, b'h', b'i'],
+            Field::Strings => vec![2, 1, b'a', 1, b'b'],
+            Field::Hashes => [&[1][..], &[5; 32]].concat(),
+            // Increment and Set(100)
+            Field::Ops => vec![2, 1, 0, 5, 2, 100, 0, 0, 0],
+            Field::Op => vec![0],
+        }
+    }
+
+    #[test]
+    fn test_layouts_match_pack() {
+        for (i, (name, tag, fields)) in INSTRUCTIONS.iter().enumerate() {
+            assert_eq!(*tag as usize, i, "{}", name);
+            let mut data = vec![*tag];
+            for (_, field) in fields.iter() {
+                data.extend(sample(*field));
+            }
+            let instruction = HelloInstruction::unpack(&data).unwrap();
+            let debug = format!("{:?}", instruction);
+            assert_eq!(debug.split('(').next(), Some(*name));
+            assert_eq!(instruction.pack(), data, "{}", name);
+        }
+        // and there isn't an instruction the table doesn't have
+        assert!(HelloInstruction::unpack(&[INSTRUCTIONS.len() as u8]).is_err());
+
+        let data: Vec<u8> = GREETING_ACCOUNT
+            .iter()
+            .flat_map(|(_, field)| sample(*field))
+            .collect();
+        assert_eq!(data.len(), GreetingAccount::LEN);
+        assert_eq!(GreetingAccount::try_from_slice(&data).unwrap().counter, 100);
+    }
+
+    #[test]
+    fn test_typescript_is_current() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../client/hello_types.ts");
+        let file = fs::read_to_string(&path).unwrap();
+        assert!(
+            file == typescript(),
+            "{} is out of date, run `npm run codegen`",
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,513 @@
+//src/program-rust/client/src/lib.rs
+// Off-chain side of the hello world program: building the transactions a
+// client sends and decoding the accounts it reads back.
+//
+// Nothing here talks to a cluster itself, pass the transactions to whichever
+// RPC or BanksClient the application already has. The instruction builders
+// are in hello_types::instruction, re-exported below.
+
+// solana-sdk doesn't build for BPF, programs use hello-types directly
+#[cfg(target_arch = "bpf")]
+compile_error!("hello-client is off-chain only, depend on hello-types from a program");
+
+pub mod codegen;
+
+use borsh::BorshDeserialize;
+use hello_types::metrics::{MetricsRecord, METRICS_TAG};
+use hello_types::state::{read_counter, Allowlist, GreetingAccount, GreetingMetadata, Vault};
+use solana_sdk::{
+    account::Account,
+    compute_budget,
+    hash::Hash,
+    instruction::Instruction,
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+
+pub use hello_types::{
+    config, discriminator, error, instruction, layout, merkle, metrics, seeds, state,
+};
+
+/// System program instruction creating `greeting` as a zeroed greeting
+/// account owned by `program_id`, funded with `lamports` from `payer`.
+/// The rent exempt minimum for GreetingAccount::LEN bytes is what the
+/// TypeScript client pays.
+pub fn create_greeting(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    greeting: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    system_instruction::create_account(
+        payer,
+        greeting,
+        lamports,
+        GreetingAccount::LEN as u64,
+        program_id,
+    )
+}
+
+/// `instructions` in one transaction paid for by `payer`, signed by the
+/// payer and any extra `signers` (a new greeting account, a poll authority)
+pub fn transaction(
+    instructions: &[Instruction],
+    payer: &Keypair,
+    signers: &[&Keypair],
+    recent_blockhash: Hash,
+) -> Transaction {
+    let mut all_signers = vec![payer];
+    all_signers.extend_from_slice(signers);
+    Transaction::new_signed_with_payer(
+        instructions,
+        Some(&payer.pubkey()),
+        &all_signers,
+        recent_blockhash,
+    )
+}
+
+/// `instructions` after a ComputeBudget request for a heap of
+/// config::HEAP_FRAME_LEN bytes. A program built with `custom-heap`
+/// allocates from that heap and can't allocate at all in a transaction
+/// without the request, so every transaction sent to one needs this.
+pub fn with_heap_frame(instructions: &[Instruction]) -> Vec<Instruction> {
+    let mut with_heap = vec![compute_budget::request_heap_frame(
+        config::HEAP_FRAME_LEN as u32,
+    )];
+    with_heap.extend_from_slice(instructions);
+    with_heap
+}
+
+/// `instructions` for a program built with `require-memo`: an SPL Memo of
+/// `memo` goes first, and the instructions to `program_id` that increment a
+/// counter get the instructions sysvar the program looks for it in.
+pub fn with_memo(
+    program_id: &Pubkey,
+    instructions: &[Instruction],
+    memo: &str,
+) -> Vec<Instruction> {
+    let mut with_memo = vec![Instruction::new_with_bytes(
+        config::memo::id(),
+        memo.as_bytes(),
+        vec![],
+    )];
+    with_memo.extend(instructions.iter().map(|ix| {
+        let increments = ix.program_id == *program_id
+            && matches!(discriminator::decode(&ix.data), Ok(op) if op.increments());
+        if increments {
+            instruction::with_instructions_sysvar(ix.clone())
+        } else {
+            ix.clone()
+        }
+    }));
+    with_memo
+}
+
+/// Compute units pack_increments() lets a transaction use, the default
+/// budget
+pub const MAX_TRANSACTION_UNITS: u64 = 200_000;
+
+/// One Increment for each of `greetings`, packed in order into as few
+/// transactions as fit in a packet and in MAX_TRANSACTION_UNITS, at
+/// `units_per_increment` each (see the profile binary for a figure). The
+/// transactions are paid for by `payer` and left for the caller to sign
+/// with a recent blockhash.
+///
+/// Address lookup tables would fit more greetings per transaction, but
+/// they need versioned transactions, which solana-sdk 1.8 doesn't have.
+pub fn pack_increments(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    greetings: &[Pubkey],
+    units_per_increment: u64,
+) -> Vec<Transaction> {
+    let per_transaction = (MAX_TRANSACTION_UNITS / units_per_increment.max(1)).max(1);
+    let mut transactions = vec![];
+    let mut batch: Vec<Instruction> = vec![];
+    for greeting in greetings {
+        let increment = instruction::increment(program_id, greeting);
+        if !batch.is_empty() {
+            batch.push(increment.clone());
+            let fits = batch.len() as u64 <= per_transaction
+                && transaction_size(&Message::new(&batch, Some(payer))) <= PACKET_DATA_SIZE;
+            batch.pop();
+            if !fits {
+                transactions.push(Transaction::new_with_payer(&batch, Some(payer)));
+                batch.clear();
+            }
+        }
+        batch.push(increment);
+    }
+    if !batch.is_empty() {
+        transactions.push(Transaction::new_with_payer(&batch, Some(payer)));
+    }
+    transactions
+}
+
+// Size on the wire of a signed transaction with `message`: the signature
+// count as a compact u16, the signatures, then the message
+fn transaction_size(message: &Message) -> usize {
+    let signatures = message.header.num_required_signatures as usize;
+    let count_len = if signatures < 0x80 { 1 } else { 2 };
+    count_len + 64 * signatures + message.serialize().len()
+}
+
+/// The counter of a greeting account fetched from the cluster. Accounts not
+/// owned by `program_id` are rejected, whatever their data looks like.
+pub fn greeting_counter(program_id: &Pubkey, account: &Account) -> Result<u32, ProgramError> {
+    if account.owner != *program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    read_counter(&account.data)
+}
+
+/// Any other account of the program, e.g. a Poll or an Attestation, decoded
+/// as `T`. Trailing bytes are allowed, records are created with room to grow.
+pub fn program_state<T: BorshDeserialize>(
+    program_id: &Pubkey,
+    account: &Account,
+) -> Result<T, ProgramError> {
+    if account.owner != *program_id {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    T::deserialize(&mut &*account.data).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+/// A getProgramAccounts filter, the same as solana-client's
+/// RpcFilterType::DataSize and RpcFilterType::Memcmp. This crate doesn't
+/// depend on solana-client, map them over to send them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AccountFilter {
+    /// The account data is exactly this many bytes
+    DataSize(u64),
+    /// The account data holds `bytes` at `offset`
+    Memcmp { offset: usize, bytes: Vec<u8> },
+}
+
+impl AccountFilter {
+    /// Whether `data` passes the filter, as the RPC node would decide
+    pub fn matches(&self, data: &[u8]) -> bool {
+        match self {
+            AccountFilter::DataSize(size) => data.len() as u64 == *size,
+            AccountFilter::Memcmp { offset, bytes } => {
+                data.get(*offset..offset + bytes.len()) == Some(&bytes[..])
+            }
+        }
+    }
+}
+
+/// Filters selecting the greeting accounts among the program's accounts.
+/// No other account type is GreetingAccount::LEN bytes, so the size alone
+/// tells them apart.
+pub fn greeting_filters() -> Vec<AccountFilter> {
+    vec![AccountFilter::DataSize(GreetingAccount::LEN as u64)]
+}
+
+/// Filters selecting the vaults of `authority`, across every greeting
+pub fn vault_filters(authority: &Pubkey) -> Vec<AccountFilter> {
+    authority_filters(Vault::LEN, Vault::AUTHORITY_OFFSET, authority)
+}
+
+/// Filters selecting the metadata records `authority` may change
+pub fn metadata_filters(authority: &Pubkey) -> Vec<AccountFilter> {
+    authority_filters(
+        GreetingMetadata::LEN,
+        GreetingMetadata::AUTHORITY_OFFSET,
+        authority,
+    )
+}
+
+/// Filters selecting the allowlists `authority` may change the root of
+pub fn allowlist_filters(authority: &Pubkey) -> Vec<AccountFilter> {
+    authority_filters(Allowlist::LEN, Allowlist::AUTHORITY_OFFSET, authority)
+}
+
+// The size picks the account type, the memcmp the authority within it
+fn authority_filters(len: usize, offset: usize, authority: &Pubkey) -> Vec<AccountFilter> {
+    vec![
+        AccountFilter::DataSize(len as u64),
+        AccountFilter::Memcmp {
+            offset,
+            bytes: authority.to_bytes().to_vec(),
+        },
+    ]
+}
+
+/// The MetricsRecords logged by the program in `logs`, a transaction's log
+/// messages, in the order they ran. Log data from other programs is skipped,
+/// as are records too old for this client to read.
+pub fn decode_metrics(logs: &[String]) -> Vec<MetricsRecord> {
+    logs.iter()
+        .filter_map(|line| line.strip_prefix("Program data: "))
+        .filter_map(|data| {
+            let mut fields = data.split(' ');
+            let tag = base64::decode(fields.next()?).ok()?;
+            if tag != METRICS_TAG {
+                return None;
+            }
+            MetricsRecord::unpack(&base64::decode(fields.next()?).ok()?)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hello_types::state::Attestation;
+    use std::slice;
+
+    #[test]
+    fn test_decode_checks_owner() {
+        let program_id = Pubkey::new_unique();
+        let greeting = Account {
+            lamports: 1,
+            data: 9u32.to_le_bytes().to_vec(),
+            owner: program_id,
+            ..Account::default()
+        };
+        assert_eq!(greeting_counter(&program_id, &greeting), Ok(9));
+        assert_eq!(
+            greeting_counter(&Pubkey::new_unique(), &greeting),
+            Err(ProgramError::IncorrectProgramId)
+        );
+
+        // too short to be an attestation
+        assert_eq!(
+            program_state::<Attestation>(&program_id, &greeting),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_account_filters() {
+        use borsh::BorshSerialize;
+        use hello_types::state::{
+            Badge, GreeterStats, PendingSet, Poll, RecentOps, Streak, VoteReceipt,
+        };
+
+        let authority = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let greeting = Pubkey::new_unique();
+        let vault = |authority| {
+            let vault = Vault {
+                greeting,
+                authority,
+                target: 5,
+            };
+            vault.try_to_vec().unwrap()
+        };
+        let allowlist = |authority| {
+            let allowlist = Allowlist {
+                greeting,
+                authority,
+                root: [7; 32],
+            };
+            allowlist.try_to_vec().unwrap()
+        };
+        // created GreetingMetadata::LEN long whatever the URI
+        let metadata = |authority| {
+            let metadata = GreetingMetadata {
+                greeting,
+                authority,
+                language: *b"en",
+                uri: "https://example.com".into(),
+            };
+            let mut data = metadata.try_to_vec().unwrap();
+            data.resize(GreetingMetadata::LEN, 0);
+            data
+        };
+        let accounts = [
+            vec![0; GreetingAccount::LEN],
+            vault(authority),
+            vault(other),
+            allowlist(authority),
+            allowlist(other),
+            metadata(authority),
+            metadata(other),
+        ];
+        let selected = |filters: Vec<AccountFilter>| -> Vec<usize> {
+            (0..accounts.len())
+                .filter(|&i| filters.iter().all(|filter| filter.matches(&accounts[i])))
+                .collect()
+        };
+        assert_eq!(selected(greeting_filters()), [0]);
+        assert_eq!(selected(vault_filters(&authority)), [1]);
+        assert_eq!(selected(allowlist_filters(&authority)), [3]);
+        assert_eq!(selected(metadata_filters(&authority)), [5]);
+
+        // the size alone picks out greetings
+        let others = [
+            VoteReceipt::LEN,
+            Attestation::LEN,
+            RecentOps::LEN,
+            Vault::LEN,
+            Badge::LEN,
+            Streak::LEN,
+            PendingSet::LEN,
+            Allowlist::LEN,
+            GreeterStats::LEN,
+            GreetingMetadata::LEN,
+            Poll::LEN,
+        ];
+        assert!(!others.contains(&GreetingAccount::LEN));
+
+        // bytes past the end of the data don't match
+        let memcmp = AccountFilter::Memcmp {
+            offset: 2,
+            bytes: vec![0; 4],
+        };
+        assert!(!memcmp.matches(&[0; 4]));
+    }
+
+    #[test]
+    fn test_transaction_is_signed_by_all() {
+        let program_id = Pubkey::new_unique();
+        let payer = Keypair::new();
+        let greeting = Keypair::new();
+        let tx = transaction(
+            &[
+                create_greeting(&program_id, &payer.pubkey(), &greeting.pubkey(), 1),
+                instruction::increment(&program_id, &greeting.pubkey()),
+            ],
+            &payer,
+            &[&greeting],
+            Hash::default(),
+        );
+        assert!(tx.is_signed());
+        assert_eq!(tx.message.account_keys[0], payer.pubkey());
+    }
+
+    #[test]
+    fn test_with_heap_frame() {
+        let program_id = Pubkey::new_unique();
+        let increment = instruction::increment(&program_id, &Pubkey::new_unique());
+        let instructions = with_heap_frame(slice::from_ref(&increment));
+        // the runtime only reads requests among the first instructions
+        assert_eq!(instructions[0].program_id, compute_budget::id());
+        assert!(instructions[0].accounts.is_empty());
+        // RequestHeapFrame(256 KiB)
+        assert_eq!(instructions[0].data, [1, 0, 0, 4, 0]);
+        assert_eq!(instructions[1..], [increment]);
+    }
+
+    #[test]
+    fn test_with_memo() {
+        use hello_types::instruction::HelloInstruction;
+
+        let program_id = Pubkey::new_unique();
+        let greeting = Pubkey::new_unique();
+        let increment = instruction::increment(&program_id, &greeting);
+        let decrement = instruction::decrement(&program_id, &greeting);
+        let batch = instruction::batch(
+            &program_id,
+            &greeting,
+            vec![HelloInstruction::Decrement, HelloInstruction::Increment],
+        );
+        let other = instruction::increment(&Pubkey::new_unique(), &greeting);
+        let instructions = with_memo(
+            &program_id,
+            &[
+                increment.clone(),
+                decrement.clone(),
+                batch.clone(),
+                other.clone(),
+            ],
+            "invoice 42",
+        );
+
+        assert_eq!(instructions[0].program_id, config::memo::id());
+        assert_eq!(instructions[0].data, b"invoice 42");
+        assert_eq!(
+            instructions[1..],
+            [
+                instruction::with_instructions_sysvar(increment),
+                decrement,
+                instruction::with_instructions_sysvar(batch),
+                other,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pack_increments() {
+        let program_id = Pubkey::new_unique();
+        let payer = Keypair::new();
+        let greetings: Vec<Pubkey> = (0..100).map(|_| Pubkey::new_unique()).collect();
+        let increments = |greetings: &[Pubkey]| -> Vec<Instruction> {
+            greetings
+                .iter()
+                .map(|greeting| instruction::increment(&program_id, greeting))
+                .collect()
+        };
+
+        // limited by size, every transaction but the last is full
+        let packed = pack_increments(&program_id, &payer.pubkey(), &greetings, 1_000);
+        assert!(packed.len() > 1);
+        let mut start = 0;
+        for tx in &packed {
+            let mut tx = tx.clone();
+            tx.sign(&[&payer], Hash::default());
+            assert!(bincode::serialize(&tx).unwrap().len() <= PACKET_DATA_SIZE);
+            let count = tx.message.instructions.len();
+            let expected = Message::new(
+                &increments(&greetings[start..start + count]),
+                Some(&payer.pubkey()),
+            );
+            assert_eq!(tx.message, expected);
+
+            start += count;
+            if start < greetings.len() {
+                let mut bigger = tx.clone();
+                bigger.message = Message::new(
+                    &increments(&greetings[start - count..=start]),
+                    Some(&payer.pubkey()),
+                );
+                bigger.sign(&[&payer], Hash::default());
+                assert!(bincode::serialize(&bigger).unwrap().len() > PACKET_DATA_SIZE);
+            }
+        }
+        assert_eq!(start, greetings.len());
+
+        // limited by compute units
+        let packed = pack_increments(&program_id, &payer.pubkey(), &greetings, 50_000);
+        assert_eq!(packed.len(), 25);
+        assert!(packed.iter().all(|tx| tx.message.instructions.len() == 4));
+
+        assert!(pack_increments(&program_id, &payer.pubkey(), &[], 1_000).is_empty());
+    }
+
+    #[test]
+    fn test_decode_metrics() {
+        use borsh::BorshSerialize;
+
+        let increment = MetricsRecord::new(&[0], 1, 7, 0);
+        let failed = MetricsRecord::new(
+            &[2, 1],
+            1,
+            7,
+            u64::from(ProgramError::from(
+                hello_types::error::HelloError::InvalidInstruction,
+            )),
+        );
+        // what the runtime logs for sol_log_data(&[METRICS_TAG, record])
+        let line = |record: &MetricsRecord| {
+            format!(
+                "Program data: {} {}",
+                base64::encode(METRICS_TAG),
+                base64::encode(record.try_to_vec().unwrap())
+            )
+        };
+        let logs = vec![
+            "Program 11111111111111111111111111111111 invoke [1]".to_string(),
+            line(&increment),
+            format!("Program data: {}", base64::encode(b"someone else")),
+            "Program log: Greeted 1 time(s)!".to_string(),
+            line(&failed),
+        ];
+        assert_eq!(decode_metrics(&logs), vec![increment, failed]);
+    }
+}
@@ -0,0 +1,1163 @@
+//src/program-rust/src/accounts.rs
+// Accounts contexts: one struct per account layout an instruction expects.
+//
+// Each constructor walks the accounts slice in the exact order the
+// instruction documents, runs the checks for every position and then makes
+// sure nothing is left over. Too few accounts is NotEnoughAccountKeys, extra
+// accounts are HelloError::TooManyAccounts instead of being silently ignored.
+//
+// Contexts with more than one writable account pass them through
+// check_no_duplicate_writable(), because the same account in two writable
+// positions aliases one RefCell and would double count any transfer between
+// them.
+//
+// The per-position checks are written with the accounts! macro below, so
+// each context states its constraints in one line per account.
+//
+// Sysvar accounts are only accepted through next_sysvar_account(), which
+// compares the key with the canonical sysvar id, so a caller can't hand in
+// an ordinary account filled with fake sysvar data. The key is all there is
+// to check: the runtime alone writes to the sysvar addresses, and the owner
+// isn't reliable, 1.8 gives programs the instructions sysvar with the default
+// one. Handlers that only need the value and not the account should prefer
+// Sysvar::get().
+
+use crate::config;
+use crate::error::HelloError;
+use crate::instruction::MAX_GET_MULTIPLE_LEN;
+use crate::logging::WithContext;
+use crate::pda;
+use crate::seeds::{
+    self, AllowlistSeeds, BadgeSeeds, GreeterStatsSeeds, PendingSetSeeds, RecentOpsSeeds,
+    StreakSeeds, VaultSeeds,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    bpf_loader_upgradeable, msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use std::slice::Iter;
+
+// Take the next account from `$iter`, an iterator over `$accounts`, for each
+// `name: constraint...` entry, binding it to `name` and running its checks,
+// in order:
+//
+// - `owned`: owned by `$program_id`, IncorrectProgramId otherwise
+// - `writable`: passed as writable, InvalidAccountData otherwise
+// - `signer`: signed the transaction, MissingRequiredSignature otherwise
+// - `system_program`: is the system program, IncorrectProgramId otherwise
+//
+// An entry with no constraints just takes the account, for positions the
+// context checks by hand (PDAs, config ids). Running out of accounts is
+// NotEnoughAccountKeys from next_account_info(), never a panic. The checks
+// are given the account's index in `$accounts` for with_context().
+macro_rules! accounts {
+    ($accounts:ident, $iter:ident, $program_id:expr; $($name:ident: $($constraint:ident)*),+ $(,)?) => {
+        $(
+            let $name = next_account_info($iter)?;
+            #[allow(unused_variables)]
+            let index = $accounts.len() - $iter.len() - 1;
+            $(accounts!(@check $name, index, $program_id, $constraint);)*
+        )+
+    };
+    (@check $account:ident, $index:ident, $program_id:expr, owned) => {
+        check_owner($account, $index, $program_id, stringify!($account))?
+    };
+    (@check $account:ident, $index:ident, $program_id:expr, writable) => {
+        check_writable($account, $index, stringify!($account))?
+    };
+    (@check $account:ident, $index:ident, $program_id:expr, signer) => {
+        check_signer($account, $index, stringify!($account))?
+    };
+    (@check $account:ident, $index:ident, $program_id:expr, system_program) => {
+        check_system_program($account, $index)?
+    };
+}
+
+/// Accounts for Increment, Decrement and Set
+///
+/// 0. `[writable]` The greeting account, owned by this program
+/// 1. `[]` Optional, the greeting's metadata record,
+///    pda::find_metadata_address(greeting), to log in its language
+/// 2. `[]` Optional, the instructions sysvar, which Increment needs in a
+///    `require-memo` build
+pub struct GreetingContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub metadata: Option<&'a AccountInfo<'info>>,
+    pub instructions: Option<&'a AccountInfo<'info>>,
+}
+
+impl<'a, 'info> GreetingContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        // Iterating accounts is safer than indexing
+        // even though accounts is only borrowing or referecing an array with the
+        // iter() function we are asking for a mutable account element of the accounts
+        // array. So in Rust we are allowed to ask for mutable reference to a variable
+        // even though accounts array was just an immutable refernce
+        // iter() function creates an iterator over the &accounts array
+        let accounts_iter = &mut accounts.iter();
+
+        // Get the account to say hello to
+        accounts!(accounts, accounts_iter, program_id; greeting: owned writable);
+
+        // the only accounts allowed after the greeting are its metadata
+        // record and then the instructions sysvar, anything else is
+        // reported as one account too many. The sysvar is recognized by
+        // its key first, so an Increment with only the sysvar after it, as
+        // in every `require-memo` transaction, doesn't derive the metadata
+        // address.
+        let metadata = match accounts_iter.as_slice().first() {
+            Some(account) if account.key == &sysvar::instructions::id() => None,
+            Some(account)
+                if account.key == &pda::find_metadata_address(program_id, greeting.key).0 =>
+            {
+                accounts_iter.next()
+            }
+            _ => None,
+        };
+        let instructions = next_sysvar_account(accounts_iter, &sysvar::instructions::id());
+
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            metadata,
+            instructions,
+        })
+    }
+}
+
+/// Accounts for Batch
+///
+/// 0. `[writable]` The greeting account, owned by this program
+/// 1. `[]` This program, needed to CPI into itself
+/// 2. `[]` Optional, the instructions sysvar, passed on to the
+///    operations, which Increment needs in a `require-memo` build
+pub struct BatchContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub program: &'a AccountInfo<'info>,
+    pub instructions: Option<&'a AccountInfo<'info>>,
+}
+
+impl<'a, 'info> BatchContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned writable, program:);
+        if program.key != program_id {
+            msg!("Second account must be the hello world program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        let instructions = next_sysvar_account(accounts_iter, &sysvar::instructions::id());
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            program,
+            instructions,
+        })
+    }
+}
+
+/// Accounts for MirrorIncrement
+///
+/// 0. `[writable]` The greeting account, owned by this program
+/// 1. `[writable]` The greeting account to mirror to, owned by the mirror program
+/// 2. `[]` The mirror program, `config::mirror::id()`
+/// 3. `[]` Optional, the instructions sysvar, which a `require-memo`
+///    build needs, passed on to the mirror program
+pub struct MirrorContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub mirror_greeting: &'a AccountInfo<'info>,
+    pub mirror_program: &'a AccountInfo<'info>,
+    pub instructions: Option<&'a AccountInfo<'info>>,
+}
+
+impl<'a, 'info> MirrorContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id;
+            greeting: owned writable,
+            mirror_greeting:,
+            mirror_program:,
+        );
+        if !config::mirror::check_id(mirror_program.key) || mirror_program.key == program_id {
+            msg!("Mirror program must be the configured second deployment");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if !mirror_program.executable {
+            msg!("Mirror program account is not executable");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        // the mirror program checks this too, failing here is just cheaper
+        if mirror_greeting.owner != mirror_program.key {
+            msg!("Mirror greeting account is not owned by the mirror program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        if !mirror_greeting.is_writable {
+            msg!("Mirror greeting account must be writable");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        check_no_duplicate_writable(&[greeting, mirror_greeting])?;
+
+        let instructions = next_sysvar_account(accounts_iter, &sysvar::instructions::id());
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            mirror_greeting,
+            mirror_program,
+            instructions,
+        })
+    }
+}
+
+/// Accounts for CopyFrom
+///
+/// 0. `[writable]` The greeting account, owned by this program
+/// 1. `[]` The account to copy from, owned by `config::foreign::id()`
+pub struct CopyFromContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub source: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> CopyFromContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned writable);
+        // Anyone can create an account with any bytes in it, the owner is
+        // the only thing that says the data was written by the foreign
+        // program and so has the layout we are about to decode
+        accounts!(accounts, accounts_iter, program_id; source:);
+        if !config::foreign::check_id(source.owner) {
+            msg!("Source account is not owned by the configured foreign program");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self { greeting, source })
+    }
+}
+
+/// Accounts for Upvote, Downvote and Vote
+///
+/// 0. `[writable]` The account voted on, owned by this program: a greeting
+///    account for Upvote/Downvote or a Poll for Vote
+/// 1. `[writable, signer]` The voter, who also pays for the receipt
+/// 2. `[writable]` The voter's receipt, pda::find_receipt_address(target, voter)
+/// 3. `[]` The system program
+/// 4. `[]` Optional, the instructions sysvar, which Upvote needs in a
+///    `require-memo` build
+pub struct VoteContext<'a, 'info> {
+    pub target: &'a AccountInfo<'info>,
+    pub voter: &'a AccountInfo<'info>,
+    pub receipt: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    pub instructions: Option<&'a AccountInfo<'info>>,
+    /// bump seed of `receipt`
+    pub receipt_bump: u8,
+}
+
+impl<'a, 'info> VoteContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; target: owned writable, voter: signer, receipt:);
+        let (expected, receipt_bump) = pda::find_receipt_address(program_id, target.key, voter.key);
+        if receipt.key != &expected {
+            msg!("Receipt account is not the voter's receipt PDA");
+            return Err(ProgramError::InvalidSeeds).with_context(
+                "receipt address",
+                2,
+                expected,
+                receipt.key,
+            );
+        }
+        accounts!(accounts, accounts_iter, program_id; system_program: system_program);
+        check_no_duplicate_writable(&[target, voter, receipt])?;
+
+        let instructions = next_sysvar_account(accounts_iter, &sysvar::instructions::id());
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            target,
+            voter,
+            receipt,
+            system_program,
+            receipt_bump,
+            instructions,
+        })
+    }
+}
+
+/// Accounts for CreatePoll and ClosePoll
+///
+/// 0. `[writable]` The poll account, owned by this program
+/// 1. `[signer]` The poll authority. CreatePoll records it, ClosePoll
+///    checks it
+pub struct PollContext<'a, 'info> {
+    pub poll: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> PollContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; poll: owned writable, authority: signer);
+
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self { poll, authority })
+    }
+}
+
+/// Accounts for Attest
+///
+/// 0. `[]` The greeting account, owned by this program
+/// 1. `[writable, signer]` The attester, who pays for the record the first
+///    time
+/// 2. `[writable]` The record, pda::find_attestation_address(greeting)
+/// 3. `[]` The system program
+pub struct AttestContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub attester: &'a AccountInfo<'info>,
+    pub attestation: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    /// Bump seed of `attestation`, needed to sign for its creation
+    pub attestation_bump: u8,
+}
+
+impl<'a, 'info> AttestContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        // the counter isn't modified, so the greeting can be readonly
+        accounts!(accounts, accounts_iter, program_id; greeting: owned, attester: signer, attestation:);
+        let (expected, attestation_bump) = pda::find_attestation_address(program_id, greeting.key);
+        if attestation.key != &expected {
+            msg!("Attestation account is not the greeting's attestation PDA");
+            return Err(ProgramError::InvalidSeeds).with_context(
+                "attestation address",
+                2,
+                expected,
+                attestation.key,
+            );
+        }
+        accounts!(accounts, accounts_iter, program_id; system_program: system_program);
+        check_no_duplicate_writable(&[attester, attestation])?;
+
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            attester,
+            attestation,
+            system_program,
+            attestation_bump,
+        })
+    }
+}
+
+/// Accounts for SetUri and SetLanguage
+///
+/// 0. `[]` The greeting account, owned by this program
+/// 1. `[writable, signer]` The metadata authority. The first SetUri or
+///    SetLanguage makes the signer the authority and charges it for the
+///    record
+/// 2. `[writable]` The record, pda::find_metadata_address(greeting)
+/// 3. `[]` The system program
+pub struct MetadataContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    pub metadata: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    /// Bump seed of `metadata`, needed to sign for its creation
+    pub metadata_bump: u8,
+}
+
+impl<'a, 'info> MetadataContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned, authority: signer, metadata:);
+        let (expected, metadata_bump) = pda::find_metadata_address(program_id, greeting.key);
+        if metadata.key != &expected {
+            msg!("Metadata account is not the greeting's metadata PDA");
+            return Err(ProgramError::InvalidSeeds).with_context(
+                "metadata address",
+                2,
+                expected,
+                metadata.key,
+            );
+        }
+        accounts!(accounts, accounts_iter, program_id; system_program: system_program);
+        check_no_duplicate_writable(&[authority, metadata])?;
+
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            authority,
+            metadata,
+            system_program,
+            metadata_bump,
+        })
+    }
+}
+
+/// Accounts for Idempotent
+///
+/// 0. `[writable]` The greeting account, owned by this program
+/// 1. `[writable, signer]` The payer of the window the first time
+/// 2. `[writable]` The window, RecentOpsSeeds::new(greeting).pda()
+/// 3. `[]` The system program
+/// 4. `[]` Optional, the instructions sysvar, which a `require-memo`
+///    build needs when the operation is an Increment
+pub struct IdempotentContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub payer: &'a AccountInfo<'info>,
+    pub recent_ops: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    pub instructions: Option<&'a AccountInfo<'info>>,
+    /// Bump seed of `recent_ops`, needed to sign for its creation
+    pub recent_ops_bump: u8,
+}
+
+impl<'a, 'info> IdempotentContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned writable, payer: signer, recent_ops:);
+        let (expected, recent_ops_bump) = RecentOpsSeeds::new(greeting.key).pda(program_id);
+        if recent_ops.key != &expected {
+            msg!("Recent ops account is not the greeting's recent ops PDA");
+            return Err(ProgramError::InvalidSeeds).with_context(
+                "recent ops address",
+                2,
+                expected,
+                recent_ops.key,
+            );
+        }
+        accounts!(accounts, accounts_iter, program_id; system_program: system_program);
+        check_no_duplicate_writable(&[greeting, payer, recent_ops])?;
+
+        let instructions = next_sysvar_account(accounts_iter, &sysvar::instructions::id());
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            payer,
+            recent_ops,
+            system_program,
+            recent_ops_bump,
+            instructions,
+        })
+    }
+}
+
+/// Accounts for Deposit
+///
+/// 0. `[]` The greeting account, owned by this program
+/// 1. `[writable, signer]` The vault's authority, who funds it
+/// 2. `[writable]` The vault, VaultSeeds::new(greeting, authority).pda()
+/// 3. `[]` The system program
+pub struct DepositContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    pub vault: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    /// Bump seed of `vault`, needed to sign for its creation
+    pub vault_bump: u8,
+}
+
+impl<'a, 'info> DepositContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned, authority: signer, vault:);
+        let vault_bump = check_vault_address(program_id, greeting, authority, vault)?;
+        accounts!(accounts, accounts_iter, program_id; system_program: system_program);
+        check_no_duplicate_writable(&[authority, vault])?;
+
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            authority,
+            vault,
+            system_program,
+            vault_bump,
+        })
+    }
+}
+
+/// Accounts for Withdraw
+///
+/// 0. `[]` The greeting account, owned by this program
+/// 1. `[writable, signer]` The vault's authority, who gets the lamports
+/// 2. `[writable]` The vault, VaultSeeds::new(greeting, authority).pda(),
+///    opened by an earlier Deposit
+pub struct WithdrawContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    pub vault: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> WithdrawContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned, authority: signer, vault:);
+        check_vault_address(program_id, greeting, authority, vault)?;
+        // unlike Deposit the vault has to exist already
+        check_owner(vault, 2, program_id, "vault")?;
+        check_writable(vault, 2, "vault")?;
+        check_no_duplicate_writable(&[authority, vault])?;
+
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            authority,
+            vault,
+        })
+    }
+}
+
+/// Accounts for ClaimBadge
+///
+/// 0. `[]` The greeting account, owned by this program
+/// 1. `[writable, signer]` The payer of the badge
+/// 2. `[writable]` The badge, BadgeSeeds::new(greeting, milestone).pda()
+/// 3. `[]` The system program
+pub struct BadgeContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub payer: &'a AccountInfo<'info>,
+    pub badge: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    /// Bump seed of `badge`, needed to sign for its creation
+    pub badge_bump: u8,
+}
+
+impl<'a, 'info> BadgeContext<'a, 'info> {
+    /// The badge address depends on the milestone claimed, so it is
+    /// passed in from the instruction
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+        milestone: u32,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned, payer: signer, badge:);
+        let (expected, badge_bump) = BadgeSeeds::new(greeting.key, milestone).pda(program_id);
+        if badge.key != &expected {
+            msg!("Badge account is not the greeting's badge PDA for the milestone");
+            return Err(ProgramError::InvalidSeeds).with_context(
+                "badge address",
+                2,
+                expected,
+                badge.key,
+            );
+        }
+        accounts!(accounts, accounts_iter, program_id; system_program: system_program);
+        check_no_duplicate_writable(&[payer, badge])?;
+
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            payer,
+            badge,
+            system_program,
+            badge_bump,
+        })
+    }
+}
+
+/// Accounts for CheckIn
+///
+/// 0. `[writable]` The greeting account, owned by this program
+/// 1. `[writable, signer]` The user checking in, who pays for the streak
+///    the first time
+/// 2. `[writable]` The user's streak, StreakSeeds::new(greeting, user).pda()
+/// 3. `[]` The system program
+/// 4. `[]` Optional, the instructions sysvar, which a `require-memo`
+///    build needs
+pub struct CheckInContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub user: &'a AccountInfo<'info>,
+    pub streak: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    pub instructions: Option<&'a AccountInfo<'info>>,
+    /// Bump seed of `streak`, needed to sign for its creation
+    pub streak_bump: u8,
+}
+
+impl<'a, 'info> CheckInContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned writable, user: signer, streak:);
+        let (expected, streak_bump) = StreakSeeds::new(greeting.key, user.key).pda(program_id);
+        if streak.key != &expected {
+            msg!("Streak account is not the user's streak PDA for the greeting");
+            return Err(ProgramError::InvalidSeeds).with_context(
+                "streak address",
+                2,
+                expected,
+                streak.key,
+            );
+        }
+        accounts!(accounts, accounts_iter, program_id; system_program: system_program);
+        check_no_duplicate_writable(&[greeting, user, streak])?;
+
+        let instructions = next_sysvar_account(accounts_iter, &sysvar::instructions::id());
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            user,
+            streak,
+            system_program,
+            streak_bump,
+            instructions,
+        })
+    }
+}
+
+/// Accounts for CheckUpgradeAuthority
+///
+/// 0. `[]` The program's ProgramData account,
+///    seeds::program_data_address(program_id)
+pub struct ProgramDataContext<'a, 'info> {
+    pub program_data: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> ProgramDataContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; program_data:);
+        if program_data.key != &seeds::program_data_address(program_id)
+            || !bpf_loader_upgradeable::check_id(program_data.owner)
+        {
+            msg!("Account is not the program's ProgramData account");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self { program_data })
+    }
+}
+
+/// Accounts for ProposeSet
+///
+/// 0. `[]` The greeting account, owned by this program
+/// 1. `[writable, signer]` The proposer, who pays for the pending account
+/// 2. `[writable]` The pending Set, PendingSetSeeds::new(greeting).pda()
+/// 3. `[]` The system program
+pub struct ProposeSetContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub proposer: &'a AccountInfo<'info>,
+    pub pending: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    /// Bump seed of `pending`, needed to sign for its creation
+    pub pending_bump: u8,
+}
+
+impl<'a, 'info> ProposeSetContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned, proposer: signer, pending:);
+        let pending_bump = check_pending_set_address(program_id, greeting, pending)?;
+        accounts!(accounts, accounts_iter, program_id; system_program: system_program);
+        check_no_duplicate_writable(&[proposer, pending])?;
+
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            proposer,
+            pending,
+            system_program,
+            pending_bump,
+        })
+    }
+}
+
+/// Accounts for ConfirmSet
+///
+/// 0. `[writable]` The greeting account, owned by this program
+/// 1. `[writable]` The proposer recorded in the pending Set, who gets its
+///    rent back. Anyone may send the ConfirmSet.
+/// 2. `[writable]` The pending Set, PendingSetSeeds::new(greeting).pda()
+pub struct ConfirmSetContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub proposer: &'a AccountInfo<'info>,
+    pub pending: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> ConfirmSetContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned writable, proposer: writable, pending:);
+        check_pending_set_address(program_id, greeting, pending)?;
+        // only a ProposeSet creates it
+        check_owner(pending, 2, program_id, "pending")?;
+        check_writable(pending, 2, "pending")?;
+        check_no_duplicate_writable(&[greeting, proposer, pending])?;
+
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            proposer,
+            pending,
+        })
+    }
+}
+
+/// Accounts for CancelSet
+///
+/// 0. `[]` The greeting account, owned by this program
+/// 1. `[writable, signer]` The proposer recorded in the pending Set, who
+///    gets its rent back
+/// 2. `[writable]` The pending Set, PendingSetSeeds::new(greeting).pda()
+pub struct CancelSetContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub proposer: &'a AccountInfo<'info>,
+    pub pending: &'a AccountInfo<'info>,
+}
+
+impl<'a, 'info> CancelSetContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned, proposer: signer, pending:);
+        check_pending_set_address(program_id, greeting, pending)?;
+        check_owner(pending, 2, program_id, "pending")?;
+        check_writable(pending, 2, "pending")?;
+        check_no_duplicate_writable(&[proposer, pending])?;
+
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            proposer,
+            pending,
+        })
+    }
+}
+
+/// Accounts for SetAllowlist
+///
+/// 0. `[]` The greeting account, owned by this program
+/// 1. `[writable, signer]` The allowlist authority, who pays for the
+///    allowlist the first time
+/// 2. `[writable]` The allowlist, AllowlistSeeds::new(greeting).pda()
+/// 3. `[]` The system program
+pub struct AllowlistContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub authority: &'a AccountInfo<'info>,
+    pub allowlist: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    /// Bump seed of `allowlist`, needed to sign for its creation
+    pub allowlist_bump: u8,
+}
+
+impl<'a, 'info> AllowlistContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned, authority: signer, allowlist:);
+        let allowlist_bump = check_allowlist_address(program_id, greeting, allowlist)?;
+        accounts!(accounts, accounts_iter, program_id; system_program: system_program);
+        check_no_duplicate_writable(&[authority, allowlist])?;
+
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            authority,
+            allowlist,
+            system_program,
+            allowlist_bump,
+        })
+    }
+}
+
+/// Accounts for AllowlistedIncrement
+///
+/// 0. `[writable]` The greeting account, owned by this program
+/// 1. `[signer]` The allowlisted wallet
+/// 2. `[]` The allowlist, AllowlistSeeds::new(greeting).pda()
+/// 3. `[]` Optional, the instructions sysvar, which a `require-memo`
+///    build needs
+pub struct AllowlistedIncrementContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub member: &'a AccountInfo<'info>,
+    pub allowlist: &'a AccountInfo<'info>,
+    pub instructions: Option<&'a AccountInfo<'info>>,
+}
+
+impl<'a, 'info> AllowlistedIncrementContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned writable, member: signer, allowlist: owned);
+        check_allowlist_address(program_id, greeting, allowlist)?;
+
+        let instructions = next_sysvar_account(accounts_iter, &sysvar::instructions::id());
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            member,
+            allowlist,
+            instructions,
+        })
+    }
+}
+
+/// Accounts for SignedIncrement
+///
+/// 0. `[writable]` The greeting account, owned by this program
+/// 1. `[writable, signer]` The greeter, who pays for the statistics the
+///    first time
+/// 2. `[writable]` The statistics, GreeterStatsSeeds::new(greeting).pda()
+/// 3. `[]` The system program
+/// 4. `[]` Optional, the instructions sysvar, which a `require-memo`
+///    build needs
+pub struct SignedIncrementContext<'a, 'info> {
+    pub greeting: &'a AccountInfo<'info>,
+    pub greeter: &'a AccountInfo<'info>,
+    pub stats: &'a AccountInfo<'info>,
+    pub system_program: &'a AccountInfo<'info>,
+    pub instructions: Option<&'a AccountInfo<'info>>,
+    /// Bump seed of `stats`, needed to sign for its creation
+    pub stats_bump: u8,
+}
+
+impl<'a, 'info> SignedIncrementContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        accounts!(accounts, accounts_iter, program_id; greeting: owned writable, greeter: signer, stats:);
+        let (expected, stats_bump) = GreeterStatsSeeds::new(greeting.key).pda(program_id);
+        if stats.key != &expected {
+            msg!("Stats account is not the greeting's greeter stats PDA");
+            return Err(ProgramError::InvalidSeeds).with_context(
+                "stats address",
+                2,
+                expected,
+                stats.key,
+            );
+        }
+        accounts!(accounts, accounts_iter, program_id; system_program: system_program);
+        check_no_duplicate_writable(&[greeting, greeter, stats])?;
+
+        let instructions = next_sysvar_account(accounts_iter, &sysvar::instructions::id());
+        no_remaining_accounts(accounts_iter)?;
+        Ok(Self {
+            greeting,
+            greeter,
+            stats,
+            system_program,
+            stats_bump,
+            instructions,
+        })
+    }
+}
+
+/// Accounts for GetMultiple
+///
+/// 0..n. `[]` Up to MAX_GET_MULTIPLE_LEN greeting accounts, owned by this
+///    program. They are only read, so the same one may be passed more than
+///    once.
+pub struct GetMultipleContext<'a, 'info> {
+    pub greetings: &'a [AccountInfo<'info>],
+}
+
+impl<'a, 'info> GetMultipleContext<'a, 'info> {
+    pub fn load(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'info>],
+    ) -> Result<Self, ProgramError> {
+        if accounts.len() > MAX_GET_MULTIPLE_LEN {
+            msg!(
+                "GetMultiple reads at most {} accounts",
+                MAX_GET_MULTIPLE_LEN
+            );
+            return Err(HelloError::TooManyAccounts.into());
+        }
+        for (index, greeting) in accounts.iter().enumerate() {
+            check_owner(greeting, index, program_id, "greeting")?;
+        }
+        Ok(Self {
+            greetings: accounts,
+        })
+    }
+}
+
+// The PDA checks shared by two contexts. Both contexts take the PDA as
+// their third account, which is the index given to with_context().
+
+fn check_allowlist_address(
+    program_id: &Pubkey,
+    greeting: &AccountInfo,
+    allowlist: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    let (expected, bump) = AllowlistSeeds::new(greeting.key).pda(program_id);
+    if allowlist.key != &expected {
+        msg!("Allowlist account is not the greeting's allowlist PDA");
+        return Err(ProgramError::InvalidSeeds).with_context(
+            "allowlist address",
+            2,
+            expected,
+            allowlist.key,
+        );
+    }
+    Ok(bump)
+}
+
+fn check_pending_set_address(
+    program_id: &Pubkey,
+    greeting: &AccountInfo,
+    pending: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    let (expected, bump) = PendingSetSeeds::new(greeting.key).pda(program_id);
+    if pending.key != &expected {
+        msg!("Pending account is not the greeting's pending Set PDA");
+        return Err(ProgramError::InvalidSeeds).with_context(
+            "pending address",
+            2,
+            expected,
+            pending.key,
+        );
+    }
+    Ok(bump)
+}
+
+// The vault is derived from both the greeting and the authority, so a
+// matching address is all it takes to tie it to the signer. Returns its bump.
+fn check_vault_address(
+    program_id: &Pubkey,
+    greeting: &AccountInfo,
+    authority: &AccountInfo,
+    vault: &AccountInfo,
+) -> Result<u8, ProgramError> {
+    let (expected, bump) = VaultSeeds::new(greeting.key, authority.key).pda(program_id);
+    if vault.key != &expected {
+        msg!("Vault account is not the authority's vault PDA for the greeting");
+        return Err(ProgramError::InvalidSeeds).with_context(
+            "vault address",
+            2,
+            expected,
+            vault.key,
+        );
+    }
+    Ok(bump)
+}
+
+// The checks behind the accounts! constraints. `name` is the field name
+// of the account in its context, for the log line, and `index` its
+// position in the instruction's accounts.
+
+fn check_owner(
+    account: &AccountInfo,
+    index: usize,
+    program_id: &Pubkey,
+    name: &str,
+) -> Result<(), ProgramError> {
+    // only the owner may modify an account's data, and only accounts this
+    // program owns are known to hold its layouts
+    if account.owner != program_id {
+        msg!("Account `{}` does not have the correct program id", name);
+        return Err(ProgramError::IncorrectProgramId).with_context(
+            "owner",
+            index,
+            program_id,
+            account.owner,
+        );
+    }
+    Ok(())
+}
+
+fn check_writable(account: &AccountInfo, index: usize, name: &str) -> Result<(), ProgramError> {
+    if !account.is_writable {
+        msg!("Account `{}` must be writable", name);
+        return Err(ProgramError::InvalidAccountData)
+            .with_context("writable", index, "writable", "readonly");
+    }
+    Ok(())
+}
+
+fn check_signer(account: &AccountInfo, index: usize, name: &str) -> Result<(), ProgramError> {
+    if !account.is_signer {
+        msg!("Account `{}` must sign", name);
+        return Err(ProgramError::MissingRequiredSignature).with_context(
+            "signer",
+            index,
+            "a signature",
+            "none",
+        );
+    }
+    Ok(())
+}
+
+fn check_system_program(account: &AccountInfo, index: usize) -> Result<(), ProgramError> {
+    if !system_program::check_id(account.key) {
+        msg!("Expected the system program");
+        return Err(ProgramError::IncorrectProgramId).with_context(
+            "system program",
+            index,
+            system_program::id(),
+            account.key,
+        );
+    }
+    Ok(())
+}
+
+// Fail if the caller passed more accounts than the instruction takes
+fn no_remaining_accounts(accounts_iter: &mut Iter<AccountInfo>) -> Result<(), ProgramError> {
+    if accounts_iter.next().is_some() {
+        msg!("Unexpected extra accounts passed to the instruction");
+        return Err(HelloError::TooManyAccounts.into());
+    }
+    Ok(())
+}
+
+/// Fail if any key appears more than once among the given writable accounts
+pub fn check_no_duplicate_writable(writable: &[&AccountInfo]) -> Result<(), ProgramError> {
+    for (i, a) in writable.iter().enumerate() {
+        if writable[i + 1..].iter().any(|b| a.key == b.key) {
+            msg!("Account {} is passed more than once as writable", a.key);
+            return Err(HelloError::DuplicateAccount.into());
+        }
+    }
+    Ok(())
+}
+
+/// Take the next account if its key is the sysvar `expected`, for the
+/// optional sysvar positions, e.g.
+/// `next_sysvar_account(accounts_iter, &sysvar::instructions::id())`.
+/// Any other account is left for the next position.
+pub fn next_sysvar_account<'a, 'info>(
+    accounts_iter: &mut Iter<'a, AccountInfo<'info>>,
+    expected: &Pubkey,
+) -> Option<&'a AccountInfo<'info>> {
+    match accounts_iter.as_slice().first() {
+        Some(account) if account.key == expected => accounts_iter.next(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::TestAccount;
+
+    #[test]
+    fn test_sysvar_key_must_match() {
+        let clock_id = sysvar::clock::id();
+        let mut real = TestAccount::new(&sysvar::id()).key(clock_id);
+        // other keys, owned by the sysvar program or not
+        let mut other = TestAccount::new(&sysvar::id());
+        let mut spoofed = TestAccount::new(&Pubkey::new_unique());
+        let accounts = [real.info(), other.info(), spoofed.info()];
+
+        // the sysvar is taken, anything else is left in place
+        let accounts_iter = &mut accounts.iter();
+        assert_eq!(
+            next_sysvar_account(accounts_iter, &clock_id).map(|a| *a.key),
+            Some(clock_id)
+        );
+        for _ in 0..2 {
+            assert!(next_sysvar_account(accounts_iter, &clock_id).is_none());
+            accounts_iter.next();
+        }
+        assert!(next_sysvar_account(accounts_iter, &clock_id).is_none());
+    }
+
+    #[test]
+    fn test_duplicate_writable_accounts_rejected() {
+        let program_id = Pubkey::new_unique();
+        let mut a = TestAccount::new(&program_id).writable();
+        let mut b = TestAccount::new(&program_id).writable();
+        let (a, b) = (a.info(), b.info());
+
+        assert_eq!(check_no_duplicate_writable(&[&a, &b]), Ok(()));
+        assert_eq!(
+            check_no_duplicate_writable(&[&a, &b, &a]),
+            Err(HelloError::DuplicateAccount.into())
+        );
+    }
+
+    // each accounts! constraint fails with its own error
+    #[test]
+    fn test_account_constraints() {
+        let program_id = Pubkey::new_unique();
+        let mut poll = TestAccount::new(&program_id).writable();
+        let mut authority = TestAccount::wallet();
+
+        let mut readonly = TestAccount::new(&program_id);
+        let accounts = vec![readonly.info(), authority.info()];
+        assert_eq!(
+            PollContext::load(&program_id, &accounts).err(),
+            Some(ProgramError::InvalidAccountData)
+        );
+
+        let mut foreign = TestAccount::new(&Pubkey::new_unique()).writable();
+        let accounts = vec![foreign.info(), authority.info()];
+        assert_eq!(
+            PollContext::load(&program_id, &accounts).err(),
+            Some(ProgramError::IncorrectProgramId)
+        );
+
+        let mut unsigned = TestAccount::new(&system_program::id());
+        let accounts = vec![poll.info(), unsigned.info()];
+        assert_eq!(
+            PollContext::load(&program_id, &accounts).err(),
+            Some(ProgramError::MissingRequiredSignature)
+        );
+
+        let accounts = vec![poll.info()];
+        assert_eq!(
+            PollContext::load(&program_id, &accounts).err(),
+            Some(ProgramError::NotEnoughAccountKeys)
+        );
+
+        let accounts = vec![poll.info(), authority.info()];
+        assert!(PollContext::load(&program_id, &accounts).is_ok());
+    }
+}
@@ -0,0 +1,70 @@
+//src/program-rust/src/introspection.rs
+// Checks on the rest of the transaction an instruction is part of, read
+// from the instructions sysvar
+//
+// The sysvar holds every instruction of the transaction, not only the ones
+// before the current one, so a guard can't be dodged by putting the
+// instruction it looks for last.
+
+use crate::config;
+use crate::error::HelloError;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError,
+    sysvar::instructions::load_instruction_at_checked,
+};
+
+/// Fail with HelloError::MissingMemo unless the transaction also calls the
+/// SPL Memo program. `instructions` is checked to be the instructions
+/// sysvar.
+pub fn require_memo(instructions: &AccountInfo) -> ProgramResult {
+    for index in 0.. {
+        let instruction = match load_instruction_at_checked(index, instructions) {
+            Ok(instruction) => instruction,
+            // past the last instruction of the transaction
+            Err(ProgramError::InvalidArgument) => break,
+            Err(e) => return Err(e),
+        };
+        if config::memo::check_id(&instruction.program_id)
+            || config::memo_v1::check_id(&instruction.program_id)
+        {
+            return Ok(());
+        }
+    }
+    msg!("The transaction must include an SPL Memo instruction");
+    Err(HelloError::MissingMemo.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::TestAccount;
+    use solana_program::{instruction::Instruction, pubkey::Pubkey};
+    use std::slice;
+
+    #[test]
+    fn test_require_memo() {
+        let program_id = Pubkey::new_unique();
+        let increment = crate::instruction::increment(&program_id, &Pubkey::new_unique());
+        let memo = Instruction::new_with_bytes(config::memo::id(), b"invoice 42", vec![]);
+        let memo_v1 = Instruction::new_with_bytes(config::memo_v1::id(), b"invoice 42", vec![]);
+
+        let mut alone = TestAccount::instructions_sysvar(slice::from_ref(&increment));
+        assert_eq!(
+            require_memo(&alone.info()),
+            Err(HelloError::MissingMemo.into())
+        );
+
+        // before or after the increment, either memo program
+        let mut after = TestAccount::instructions_sysvar(&[increment.clone(), memo.clone()]);
+        assert_eq!(require_memo(&after.info()), Ok(()));
+        let mut before = TestAccount::instructions_sysvar(&[memo_v1, increment]);
+        assert_eq!(require_memo(&before.info()), Ok(()));
+
+        // an ordinary account holding the same bytes is not the sysvar
+        let mut spoofed = TestAccount::instructions_sysvar(&[memo]).key(Pubkey::new_unique());
+        assert_eq!(
+            require_memo(&spoofed.info()),
+            Err(ProgramError::UnsupportedSysvar)
+        );
+    }
+}
@@ -1,116 +1,99 @@
-use borsh::{BorshDeserialize, BorshSerialize};
-use solana_program::{
-    account_info::{next_account_info, AccountInfo},
-    entrypoint,
-    entrypoint::ProgramResult,
-    msg,
-    program_error::ProgramError,
-    pubkey::Pubkey,
+// logging macros, declared first so every module below can use them
+#[macro_use]
+mod logging;
+
+pub mod accounts;
+pub mod introspection;
+pub mod locale;
+pub mod pda;
+pub mod processor;
+pub mod security;
+#[cfg(test)]
+mod test_utils;
+pub mod upgrade;
+
+// the instruction, state and error types live in the hello-types crate,
+// re-exported so helloworld::instruction and friends keep working
+pub use hello_types::{
+    config, discriminator, error, instruction, layout, merkle, metrics, seeds, state,
 };
 
-// import the instruction.rs
-pub mod instruction;
-use crate::instruction::HelloInstruction;
+pub use crate::processor::process_instruction;
+pub use crate::state::GreetingAccount;
 
-/// Define the type of state stored in accounts
-#[derive(BorshSerialize, BorshDeserialize, Debug)]
-pub struct GreetingAccount {
-    /// number of greetings
-    pub counter: u32,
-}
+// Declare and export the program's entrypoint. Crates linking the program
+// as a library (another program's tests, off-chain tools) build it with
+// `no-entrypoint` so they don't export a second `entrypoint` symbol.
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
 
-// Declare and export the program's entrypoint
-entrypoint!(process_instruction);
-
-// Program entrypoint's implementation
-pub fn process_instruction(
-    program_id: &Pubkey, // Public key of the account the hello world program was loaded into
-    accounts: &[AccountInfo], // The account to say hello to
-    instruction_data: &[u8], 
-) -> ProgramResult {
-    msg!("Hello World Rust program entrypoint");
-
-    // Send the instrcion_data obtained from client to the unpack function
-    // to decode data to HelloInstruction enum
-    let instruction = HelloInstruction::unpack(instruction_data)?;
-
-    // Iterating accounts is safer than indexing
-    // even though accounts is only borrowing or referecing an array with the
-    // iter() function we are asking for a mutable account element of the accounts
-    // array. So in Rust we are allowed to ask for mutable reference to a variable
-    // even though accounts array was just an immutable refernce
-    // iter() function creates an iterator over the &accounts array
-    let accounts_iter = &mut accounts.iter();
-
-    // Get the account to say hello to
-    // using the iterator obtain the accountInfo struct of the next account 
-    // Since this is the first time we are calling the next_account_info() on
-    // accounts_iter this will be the first element of the accounts and we will
-    // get the account_info of that first account
-    // this variable should have been called account_info instead as that is what
-    // we are getting back
-    let account = next_account_info(accounts_iter)?;
-
-    // The account must be owned by the program in order to modify its data
-    if account.owner != program_id {
-        msg!("Greeted account does not have the correct program id");
-        return Err(ProgramError::IncorrectProgramId);
-    }
-
-    // Increment and store the number of times the account has been greeted
-    // de-serialize using the try_from_slice() function the reference to [u8] 
-    // in the account.data
-    // we get an instance of the struct GreetingAccount. we save it as a mutable
-    // variable to change the field counter of the struct's instance
-    let mut greeting_account = GreetingAccount::try_from_slice(&account.data.borrow())?;
-
-    // instruction is an HelloInstruction enum already desctructured from the Result
-    // Check what the instruction value unpacked to. Depending on the
-    // enum variant we do the corresponding action of incrementing or decrementing
-    // or setting the value
-    match instruction {
-        HelloInstruction::Increment => greeting_account.counter += 1,
-        HelloInstruction::Decrement => greeting_account.counter -= 1,
-        HelloInstruction::Set(value) => greeting_account.counter = value,
-    }
-
-
-    
-    // storing the data as bytes by serializing it
-    greeting_account.serialize(&mut &mut account.data.borrow_mut()[..])?;
-
-    msg!("Greeted {} time(s)!", greeting_account.counter);
-
-    Ok(())
+// With the `custom-panic` feature the default panic handler, which formats
+// the full panic message, is replaced by this one. It only logs where the
+// panic happened and only when `log-debug` is on, so production builds don't
+// carry the formatting code at all.
+#[cfg(all(
+    feature = "custom-panic",
+    not(feature = "no-entrypoint"),
+    target_arch = "bpf"
+))]
+#[no_mangle]
+fn custom_panic(info: &core::panic::PanicInfo<'_>) {
+    if let Some(location) = info.location() {
+        log_debug!("panicked at {}:{}", location.file(), location.line());
+    }
 }
 
+// With the `custom-heap` feature the default 32 KiB bump allocator is
+// replaced by one over the largest heap frame the runtime hands out,
+// config::HEAP_FRAME_LEN, for Batch and poll instructions whose Vecs outgrow
+// the default. The memory past the first 32 KiB only exists when the
+// transaction asks for it with a ComputeBudget RequestHeapFrame instruction,
+// and the bump allocator hands out memory from the top of the heap down, so
+// without one even the first allocation is out of bounds. Every transaction
+// sent to a build with this feature has to go through
+// hello_client::with_heap_frame().
+#[cfg(all(
+    feature = "custom-heap",
+    not(feature = "no-entrypoint"),
+    target_arch = "bpf"
+))]
+const HEAP_LENGTH: usize = config::HEAP_FRAME_LEN;
+
+#[cfg(all(
+    feature = "custom-heap",
+    not(feature = "no-entrypoint"),
+    target_arch = "bpf"
+))]
+#[global_allocator]
+static ALLOCATOR: solana_program::entrypoint::BumpAllocator =
+    solana_program::entrypoint::BumpAllocator {
+        start: solana_program::entrypoint::HEAP_START_ADDRESS,
+        len: HEAP_LENGTH,
+    };
+
 // Sanity tests
 #[cfg(test)]
 mod test {
     use super::*;
-    use solana_program::clock::Epoch;
+    use crate::error::HelloError;
+    use crate::test_utils::{return_data, with_memo, TestAccount};
+    use borsh::BorshDeserialize;
+    use solana_program::{program_error::ProgramError, pubkey::Pubkey};
     use std::mem;
 
     #[test]
     fn test_sanity() {
         let program_id = Pubkey::default();
-        let key = Pubkey::default();
-        let mut lamports = 0;
-        let mut data = vec![0; mem::size_of::<u32>()];
-        let owner = Pubkey::default();
-        let account = AccountInfo::new(
-            &key,
-            false,
-            true,
-            &mut lamports,
-            &mut data,
-            &owner,
-            false,
-            Epoch::default(),
-        );
-        let instruction_data: Vec<u8> = Vec::new();
+        let mut account = TestAccount::new(&Pubkey::default())
+            .key(Pubkey::default())
+            .data_len(mem::size_of::<u32>())
+            .writable();
+        // tag 0 is HelloInstruction::Increment
+        let instruction_data: Vec<u8> = vec![0];
 
-        let accounts = vec![account];
+        let mut memo = TestAccount::memo_sysvar();
+        let memo = memo.info();
+        let accounts = with_memo(vec![account.info()], &memo);
 
         assert_eq!(
             GreetingAccount::try_from_slice(&accounts[0].data.borrow())
@@ -133,4 +116,1027 @@ mod test {
             2
         );
     }
+
+    #[test]
+    fn test_fast_path_matches_borsh_layout() {
+        let program_id = Pubkey::default();
+        let mut account = TestAccount::greeting(&program_id);
+        let mut memo = TestAccount::memo_sysvar();
+        let memo = memo.info();
+        let accounts = with_memo(vec![account.info()], &memo);
+
+        // Set goes through Borsh, Increment/Decrement through the fast path
+        process_instruction(&program_id, &accounts, &[2, 41, 0, 0, 0]).unwrap();
+        process_instruction(&program_id, &accounts, &[0]).unwrap();
+        process_instruction(&program_id, &accounts, &[0]).unwrap();
+        process_instruction(&program_id, &accounts, &[1]).unwrap();
+        assert_eq!(
+            GreetingAccount::try_from_slice(&accounts[0].data.borrow())
+                .unwrap()
+                .counter,
+            42
+        );
+    }
+
+    #[test]
+    fn test_fast_path_rejects_wrong_length() {
+        let program_id = Pubkey::default();
+        let mut account = TestAccount::new(&program_id)
+            .data_len(GreetingAccount::LEN + 1)
+            .writable();
+        let mut memo = TestAccount::memo_sysvar();
+        let memo = memo.info();
+        let accounts = with_memo(vec![account.info()], &memo);
+
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &[0]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_counter_bounds_return_errors() {
+        let program_id = Pubkey::default();
+        let mut account = TestAccount::greeting(&program_id);
+        let mut memo = TestAccount::memo_sysvar();
+        let memo = memo.info();
+        let accounts = with_memo(vec![account.info()], &memo);
+
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &[1]),
+            Err(HelloError::CounterUnderflow.into())
+        );
+        process_instruction(&program_id, &accounts, &[2, 0xff, 0xff, 0xff, 0xff]).unwrap();
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &[0]),
+            Err(HelloError::CounterOverflow.into())
+        );
+        // short Set payloads are an error, not an out of bounds slice
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &[2, 1]),
+            Err(HelloError::InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    fn test_account_count_is_strict() {
+        let program_id = Pubkey::default();
+        let mut account = TestAccount::greeting(&program_id);
+        let account = account.info();
+
+        assert_eq!(
+            process_instruction(&program_id, &[], &[0]),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        let accounts = vec![account.clone(), account];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &[0]),
+            Err(HelloError::TooManyAccounts.into())
+        );
+    }
+
+    // Subtracting never needs the memo, anything that adds to the counter
+    // does, see test_increments_require_memo in tests/lib.rs for the others
+    #[cfg(feature = "require-memo")]
+    #[test]
+    fn test_increment_requires_memo() {
+        use crate::state::read_counter;
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::greeting(&program_id);
+        let increment = crate::instruction::increment(&program_id, &greeting.key);
+        let mut no_memo = TestAccount::instructions_sysvar(&[increment]);
+        let mut memo = TestAccount::memo_sysvar();
+        let (greeting, no_memo, memo) = (greeting.info(), no_memo.info(), memo.info());
+
+        assert_eq!(
+            process_instruction(&program_id, &[greeting.clone()], &[0]),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        assert_eq!(
+            process_instruction(&program_id, &[greeting.clone(), no_memo], &[0]),
+            Err(HelloError::MissingMemo.into())
+        );
+        process_instruction(&program_id, &[greeting.clone(), memo], &[0]).unwrap();
+        process_instruction(&program_id, &[greeting.clone()], &[1]).unwrap();
+        assert_eq!(read_counter(&greeting.data.borrow()), Ok(0));
+    }
+
+    #[test]
+    fn test_get_version() {
+        use crate::state::VersionInfo;
+
+        let (result, data) = return_data(|| process_instruction(&Pubkey::default(), &[], &[3]));
+        result.unwrap();
+        let version = VersionInfo::try_from_slice(&data).unwrap();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(version.layout_version, GreetingAccount::LAYOUT_VERSION);
+    }
+
+    #[test]
+    fn test_get_multiple() {
+        use crate::instruction::MAX_GET_MULTIPLE_LEN;
+
+        let program_id = Pubkey::new_unique();
+        let mut greetings: Vec<_> = [3u32, 0, u32::MAX]
+            .iter()
+            .map(|counter| TestAccount::new(&program_id).data(counter.to_le_bytes().to_vec()))
+            .collect();
+        let mut accounts: Vec<_> = greetings.iter_mut().map(TestAccount::info).collect();
+        // the same greeting twice is fine, nothing is written
+        accounts.push(accounts[0].clone());
+        let (result, data) = return_data(|| process_instruction(&program_id, &accounts, &[26]));
+        result.unwrap();
+        assert_eq!(
+            Vec::<u32>::try_from_slice(&data).unwrap(),
+            [3, 0, u32::MAX, 3]
+        );
+
+        // no accounts is an empty list
+        let (result, data) = return_data(|| process_instruction(&program_id, &[], &[26]));
+        result.unwrap();
+        assert!(Vec::<u32>::try_from_slice(&data).unwrap().is_empty());
+
+        let mut foreign = TestAccount::greeting(&Pubkey::new_unique());
+        let mut short = TestAccount::new(&program_id).data_len(GreetingAccount::LEN - 1);
+        let checks = vec![
+            (foreign.info(), ProgramError::IncorrectProgramId),
+            (short.info(), ProgramError::InvalidAccountData),
+        ];
+        for (bad, err) in checks {
+            let accounts = vec![accounts[0].clone(), bad];
+            assert_eq!(process_instruction(&program_id, &accounts, &[26]), Err(err));
+        }
+
+        let accounts = vec![accounts[0].clone(); MAX_GET_MULTIPLE_LEN + 1];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &[26]),
+            Err(HelloError::TooManyAccounts.into())
+        );
+    }
+
+    #[test]
+    fn test_batch_encoding() {
+        use crate::instruction::HelloInstruction;
+
+        let batch = HelloInstruction::Batch(vec![
+            HelloInstruction::Increment,
+            HelloInstruction::Set(7),
+            HelloInstruction::Decrement,
+        ]);
+        let data = batch.pack();
+        assert_eq!(data, vec![4, 3, 1, 0, 5, 2, 7, 0, 0, 0, 1, 1]);
+        assert_eq!(HelloInstruction::unpack(&data).unwrap(), batch);
+
+        // batches can't nest, and entries can't run past the end of the data
+        let nested = HelloInstruction::Batch(vec![HelloInstruction::Batch(vec![])]).pack();
+        assert_eq!(
+            HelloInstruction::unpack(&nested),
+            Err(HelloError::InvalidInstruction.into())
+        );
+        assert_eq!(
+            HelloInstruction::unpack(&[4, 1, 5, 2, 7]),
+            Err(HelloError::InvalidInstruction.into())
+        );
+    }
+
+    // Past MAX_BATCH_LEN unpack() refuses the batch, and past 255 ops its
+    // count would wrap, so pack() panics instead
+    #[test]
+    #[should_panic(expected = "a Batch carries at most 16 ops")]
+    fn test_batch_over_max_len_panics() {
+        use crate::instruction::{batch, HelloInstruction, MAX_BATCH_LEN};
+
+        let ops = (0..=MAX_BATCH_LEN)
+            .map(|_| HelloInstruction::Increment)
+            .collect();
+        batch(&Pubkey::new_unique(), &Pubkey::new_unique(), ops);
+    }
+
+    #[test]
+    fn test_unpack_is_strict() {
+        use crate::instruction::HelloInstruction;
+
+        // one of every instruction, with arguments where it takes any
+        let instructions = vec![
+            HelloInstruction::Increment,
+            HelloInstruction::Decrement,
+            HelloInstruction::Set(7),
+            HelloInstruction::GetVersion,
+            HelloInstruction::Batch(vec![HelloInstruction::Increment, HelloInstruction::Set(7)]),
+            HelloInstruction::MirrorIncrement,
+            HelloInstruction::CopyFrom,
+            HelloInstruction::Upvote,
+            HelloInstruction::Downvote,
+            HelloInstruction::CreatePoll(vec!["yes".into(), "no".into()]),
+            HelloInstruction::Vote(1),
+            HelloInstruction::ClosePoll,
+            HelloInstruction::Attest([9; 32]),
+            HelloInstruction::SetUri("https://example.com".into()),
+            HelloInstruction::SetLanguage(*b"en"),
+            HelloInstruction::Idempotent([1; 16], Box::new(HelloInstruction::Set(7))),
+            HelloInstruction::Deposit(1_000, 5),
+            HelloInstruction::Withdraw,
+            HelloInstruction::ClaimBadge(10),
+            HelloInstruction::CheckIn,
+            HelloInstruction::CheckUpgradeAuthority(true),
+            HelloInstruction::ProposeSet(7),
+            HelloInstruction::ConfirmSet,
+            HelloInstruction::SetAllowlist([2; 32]),
+            HelloInstruction::AllowlistedIncrement(vec![[3; 32], [4; 32]]),
+            HelloInstruction::SignedIncrement,
+            HelloInstruction::GetMultiple,
+            HelloInstruction::CancelSet,
+        ];
+        for (tag, instruction) in instructions.iter().enumerate() {
+            let data = instruction.pack();
+            assert_eq!(data[0] as usize, tag, "{:?}", instruction);
+            assert_eq!(HelloInstruction::unpack(&data).as_ref(), Ok(instruction));
+
+            // a byte more is trailing data, whatever the byte
+            for extra in &[0, 1, 0xff] {
+                let mut longer = data.clone();
+                longer.push(*extra);
+                assert_eq!(
+                    HelloInstruction::unpack(&longer),
+                    Err(HelloError::TrailingInstructionData.into()),
+                    "{:?}",
+                    instruction
+                );
+            }
+            // a byte less, with an argument cut short, is invalid
+            if data.len() > 1 {
+                assert_eq!(
+                    HelloInstruction::unpack(&data[..data.len() - 1]),
+                    Err(HelloError::InvalidInstruction.into()),
+                    "{:?}",
+                    instruction
+                );
+            }
+        }
+        // every tag is covered above
+        assert_eq!(
+            HelloInstruction::unpack(&[instructions.len() as u8]),
+            Err(HelloError::InvalidInstruction.into())
+        );
+
+        // Increment followed by garbage, and Set with a fifth byte
+        assert_eq!(
+            HelloInstruction::unpack(&[0, 0xde, 0xad]),
+            Err(HelloError::TrailingInstructionData.into())
+        );
+        assert_eq!(
+            HelloInstruction::unpack(&[2, 1, 2, 3, 4, 5]),
+            Err(HelloError::TrailingInstructionData.into())
+        );
+        // trailing bytes inside a Batch entry or an Idempotent op count too
+        assert_eq!(
+            HelloInstruction::unpack(&[4, 1, 2, 0, 9]),
+            Err(HelloError::TrailingInstructionData.into())
+        );
+        let mut idempotent = vec![15];
+        idempotent.extend_from_slice(&[1; 16]);
+        idempotent.extend_from_slice(&[0, 9]);
+        assert_eq!(
+            HelloInstruction::unpack(&idempotent),
+            Err(HelloError::TrailingInstructionData.into())
+        );
+    }
+
+    #[test]
+    fn test_copy_from_foreign_account() {
+        let program_id = Pubkey::new_unique();
+        let mut account = TestAccount::greeting(&program_id);
+        let mut source =
+            TestAccount::new(&crate::config::foreign::id()).data(77u32.to_le_bytes().to_vec());
+        let source_key = source.key;
+        let accounts = vec![account.info(), source.info()];
+
+        process_instruction(&program_id, &accounts, &[6]).unwrap();
+        assert_eq!(
+            GreetingAccount::try_from_slice(&accounts[0].data.borrow())
+                .unwrap()
+                .counter,
+            77
+        );
+
+        // the same bytes in an account owned by anyone else are not trusted
+        let mut spoofed = TestAccount::new(&program_id)
+            .key(source_key)
+            .data(5u32.to_le_bytes().to_vec());
+        let accounts = vec![accounts[0].clone(), spoofed.info()];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &[6]),
+            Err(ProgramError::IncorrectProgramId)
+        );
+    }
+
+    #[test]
+    fn test_vote_receipt_checks() {
+        use crate::pda::find_receipt_address;
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::greeting(&program_id);
+        let mut voter = TestAccount::wallet();
+        let (receipt_key, _) = find_receipt_address(&program_id, &greeting.key, &voter.key);
+        // a receipt already owned by the program means the vote happened
+        let mut receipt = TestAccount::new(&program_id).key(receipt_key).writable();
+        let mut system_program = TestAccount::system_program();
+        let (greeting, voter, system_program) =
+            (greeting.info(), voter.info(), system_program.info());
+
+        let accounts = vec![
+            greeting.clone(),
+            voter.clone(),
+            receipt.info(),
+            system_program.clone(),
+        ];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &[7]),
+            Err(HelloError::AlreadyVoted.into())
+        );
+
+        // the receipt has to be this voter's PDA for this greeting
+        let accounts = vec![greeting, voter.clone(), voter, system_program];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &[8]),
+            Err(ProgramError::InvalidSeeds)
+        );
+    }
+
+    #[test]
+    fn test_poll_lifecycle_checks() {
+        use crate::instruction::HelloInstruction;
+        use crate::pda::find_receipt_address;
+        use crate::state::Poll;
+
+        let program_id = Pubkey::new_unique();
+        let mut poll = TestAccount::new(&program_id).data_len(Poll::LEN).writable();
+        let mut authority = TestAccount::wallet();
+        let authority_key = authority.key;
+        let (receipt_key, _) = find_receipt_address(&program_id, &poll.key, &authority.key);
+        let mut receipt = TestAccount::new(&solana_program::system_program::id())
+            .key(receipt_key)
+            .writable();
+        let mut system_program = TestAccount::system_program();
+        let (poll, authority) = (poll.info(), authority.info());
+        let poll_accounts = vec![poll.clone(), authority.clone()];
+        let vote_accounts = vec![
+            poll.clone(),
+            authority.clone(),
+            receipt.info(),
+            system_program.info(),
+        ];
+
+        let create = HelloInstruction::CreatePoll(vec!["yes".to_string(), "no".to_string()]);
+        process_instruction(&program_id, &poll_accounts, &create.pack()).unwrap();
+        assert_eq!(
+            process_instruction(&program_id, &poll_accounts, &create.pack()),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+        let state = Poll::unpack_initialized(&poll.data.borrow()).unwrap();
+        assert_eq!(state.authority, authority_key);
+        assert_eq!(state.options[1].label, "no");
+
+        // option 2 doesn't exist, and nothing is recorded for it
+        assert_eq!(
+            process_instruction(&program_id, &vote_accounts, &[10, 2]),
+            Err(HelloError::InvalidPollOption.into())
+        );
+
+        // only the authority may close the poll
+        let mut other = TestAccount::new(&solana_program::system_program::id()).signer();
+        assert_eq!(
+            process_instruction(&program_id, &[poll.clone(), other.info()], &[11]),
+            Err(HelloError::Unauthorized.into())
+        );
+        process_instruction(&program_id, &poll_accounts, &[11]).unwrap();
+        assert_eq!(
+            process_instruction(&program_id, &vote_accounts, &[10, 0]),
+            Err(HelloError::PollClosed.into())
+        );
+        let state = Poll::unpack_initialized(&poll.data.borrow()).unwrap();
+        assert!(!state.is_open);
+        assert!(state.options.iter().all(|option| option.count == 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "a poll label is at most 32 bytes")]
+    fn test_poll_label_over_max_len_panics() {
+        use crate::instruction::create_poll;
+        use crate::state::MAX_POLL_LABEL_LEN;
+
+        // 256 bytes would pack as an empty label followed by the text
+        let labels = vec!["yes".to_string(), "n".repeat(MAX_POLL_LABEL_LEN + 1)];
+        let key = Pubkey::new_unique();
+        create_poll(&key, &key, &key, labels);
+    }
+
+    #[test]
+    fn test_attest_checks() {
+        use crate::instruction::HelloInstruction;
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::new(&program_id).data_len(GreetingAccount::LEN);
+        let mut attester = TestAccount::wallet();
+        // any account other than the greeting's attestation PDA
+        let mut wrong = TestAccount::new(&solana_program::system_program::id()).writable();
+        let mut system_program = TestAccount::system_program();
+        let (greeting, attester) = (greeting.info(), attester.info());
+
+        let attest = HelloInstruction::Attest([7; 32]).pack();
+        assert_eq!(attest.len(), 33);
+        assert_eq!(
+            HelloInstruction::unpack(&attest[..32]),
+            Err(HelloError::InvalidInstruction.into())
+        );
+
+        let accounts = vec![
+            greeting.clone(),
+            attester.clone(),
+            wrong.info(),
+            system_program.info(),
+        ];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &attest),
+            Err(ProgramError::InvalidSeeds)
+        );
+        let accounts = vec![attester, greeting];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &attest),
+            Err(ProgramError::IncorrectProgramId)
+        );
+    }
+
+    #[test]
+    fn test_greeting_metadata() {
+        use crate::instruction::HelloInstruction;
+        use crate::pda::find_metadata_address;
+        use crate::state::{GreetingMetadata, MAX_URI_LEN};
+        use borsh::BorshSerialize;
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::greeting(&program_id);
+        let mut authority = TestAccount::wallet();
+        let authority_key = authority.key;
+        let (metadata_key, _) = find_metadata_address(&program_id, &greeting.key);
+        // a record created earlier by authority_key
+        let mut record = GreetingMetadata {
+            greeting: greeting.key,
+            authority: authority_key,
+            language: *b"fr",
+            uri: "https://example.com/old-but-longer.json".to_string(),
+        }
+        .try_to_vec()
+        .unwrap();
+        record.resize(GreetingMetadata::LEN, 0);
+        let mut metadata = TestAccount::new(&program_id)
+            .key(metadata_key)
+            .data(record)
+            .writable();
+        let mut system_program = TestAccount::system_program();
+        let (greeting, metadata, system_program) =
+            (greeting.info(), metadata.info(), system_program.info());
+
+        let set_uri = HelloInstruction::SetUri("https://example.com/new.json".to_string()).pack();
+        let accounts = vec![
+            greeting.clone(),
+            authority.info(),
+            metadata.clone(),
+            system_program.clone(),
+        ];
+        process_instruction(&program_id, &accounts, &set_uri).unwrap();
+        let stored = GreetingMetadata::unpack(&metadata.data.borrow()).unwrap();
+        assert_eq!(stored.uri, "https://example.com/new.json");
+        assert_eq!(stored.authority, authority_key);
+        assert_eq!(stored.language, *b"fr");
+
+        let set_language = HelloInstruction::SetLanguage(*b"de").pack();
+        process_instruction(&program_id, &accounts, &set_language).unwrap();
+        let stored = GreetingMetadata::unpack(&metadata.data.borrow()).unwrap();
+        assert_eq!(stored.language, *b"de");
+        assert_eq!(stored.uri, "https://example.com/new.json");
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &[14, b'x', b'x']),
+            Err(HelloError::UnsupportedLanguage.into())
+        );
+
+        // Increment accepts the record after the greeting to pick the language
+        let mut memo = TestAccount::memo_sysvar();
+        let memo = memo.info();
+        let accounts = with_memo(vec![greeting.clone(), metadata.clone()], &memo);
+        process_instruction(&program_id, &accounts, &[0]).unwrap();
+        assert_eq!(
+            GreetingAccount::try_from_slice(&greeting.data.borrow())
+                .unwrap()
+                .counter,
+            1
+        );
+
+        // anyone else signing is rejected
+        let mut other = TestAccount::wallet();
+        let accounts = vec![greeting, other.info(), metadata, system_program];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &set_uri),
+            Err(HelloError::Unauthorized.into())
+        );
+
+        // pack() won't encode it
+        let mut too_long = vec![13, MAX_URI_LEN as u8 + 1];
+        too_long.resize(too_long.len() + MAX_URI_LEN + 1, b'x');
+        assert_eq!(
+            HelloInstruction::unpack(&too_long),
+            Err(HelloError::InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "a metadata URI is at most 200 bytes")]
+    fn test_uri_over_max_len_panics() {
+        use crate::instruction::set_uri;
+        use crate::state::MAX_URI_LEN;
+
+        let key = Pubkey::new_unique();
+        set_uri(&key, &key, &key, "x".repeat(MAX_URI_LEN + 1));
+    }
+
+    #[test]
+    fn test_vault_checks() {
+        use crate::instruction::HelloInstruction;
+        use crate::seeds::VaultSeeds;
+        use crate::state::{write_counter, Vault};
+        use borsh::BorshSerialize;
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::greeting(&program_id);
+        let mut authority = TestAccount::wallet();
+        let (vault_key, _) = VaultSeeds::new(&greeting.key, &authority.key).pda(&program_id);
+        // a vault opened by an earlier Deposit, unlocked at 3
+        let record = Vault {
+            greeting: greeting.key,
+            authority: authority.key,
+            target: 3,
+        };
+        let mut vault = TestAccount::new(&program_id)
+            .key(vault_key)
+            .data(record.try_to_vec().unwrap())
+            .writable();
+        vault.lamports = 1_000;
+        let mut system_program = TestAccount::system_program();
+        let (greeting, authority, vault) = (greeting.info(), authority.info(), vault.info());
+        let withdraw = HelloInstruction::Withdraw.pack();
+        let accounts = vec![greeting.clone(), authority.clone(), vault.clone()];
+
+        write_counter(&mut greeting.data.borrow_mut(), 2).unwrap();
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &withdraw),
+            Err(HelloError::VaultLocked.into())
+        );
+        // the target can't be moved by a later deposit
+        let deposit = HelloInstruction::Deposit(10, 2).pack();
+        let deposit_accounts = vec![
+            greeting.clone(),
+            authority.clone(),
+            vault.clone(),
+            system_program.info(),
+        ];
+        assert_eq!(
+            process_instruction(&program_id, &deposit_accounts, &deposit),
+            Err(ProgramError::InvalidArgument)
+        );
+        // nor can someone else's vault be emptied
+        let mut other = TestAccount::wallet();
+        let other_accounts = vec![greeting.clone(), other.info(), vault.clone()];
+        assert_eq!(
+            process_instruction(&program_id, &other_accounts, &withdraw),
+            Err(ProgramError::InvalidSeeds)
+        );
+
+        write_counter(&mut greeting.data.borrow_mut(), 3).unwrap();
+        process_instruction(&program_id, &accounts, &withdraw).unwrap();
+        assert_eq!(authority.lamports(), 1_000);
+        assert_eq!(vault.lamports(), 0);
+        assert!(vault.data.borrow().iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn test_badge_checks() {
+        use crate::instruction::HelloInstruction;
+        use crate::seeds::BadgeSeeds;
+        use crate::state::{write_counter, Badge};
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::new(&program_id).data_len(GreetingAccount::LEN);
+        let mut payer = TestAccount::wallet();
+        let (badge_key, _) = BadgeSeeds::new(&greeting.key, 10).pda(&program_id);
+        let mut unclaimed = TestAccount::new(&solana_program::system_program::id())
+            .key(badge_key)
+            .writable();
+        let mut claimed = TestAccount::new(&program_id)
+            .key(badge_key)
+            .data_len(Badge::LEN)
+            .writable();
+        let mut system_program = TestAccount::system_program();
+        let (greeting, payer, system_program) =
+            (greeting.info(), payer.info(), system_program.info());
+        let claim = HelloInstruction::ClaimBadge(10).pack();
+
+        write_counter(&mut greeting.data.borrow_mut(), 9).unwrap();
+        let accounts = vec![
+            greeting.clone(),
+            payer.clone(),
+            unclaimed.info(),
+            system_program.clone(),
+        ];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &claim),
+            Err(HelloError::MilestoneNotReached.into())
+        );
+        // the badge for 100 is at another address
+        assert_eq!(
+            process_instruction(
+                &program_id,
+                &accounts,
+                &HelloInstruction::ClaimBadge(100).pack()
+            ),
+            Err(ProgramError::InvalidSeeds)
+        );
+
+        write_counter(&mut greeting.data.borrow_mut(), 10).unwrap();
+        let accounts = vec![greeting, payer, claimed.info(), system_program];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &claim),
+            Err(ProgramError::AccountAlreadyInitialized)
+        );
+
+        // only the milestones can be claimed
+        assert_eq!(
+            HelloInstruction::unpack(&HelloInstruction::ClaimBadge(11).pack()),
+            Err(HelloError::InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    fn test_set_above_threshold_needs_confirming() {
+        use crate::instruction::HelloInstruction;
+        use crate::state::{read_counter, write_counter, SET_CONFIRM_THRESHOLD};
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::greeting(&program_id);
+        let accounts = vec![greeting.info()];
+        let set = HelloInstruction::Set(1).pack();
+
+        write_counter(&mut accounts[0].data.borrow_mut(), SET_CONFIRM_THRESHOLD).unwrap();
+        assert_eq!(process_instruction(&program_id, &accounts, &set), Ok(()));
+
+        let above = SET_CONFIRM_THRESHOLD + 1;
+        write_counter(&mut accounts[0].data.borrow_mut(), above).unwrap();
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &set),
+            Err(HelloError::ConfirmationRequired.into())
+        );
+        // nor can CopyFrom overwrite it
+        let mut source =
+            TestAccount::new(&crate::config::foreign::id()).data(1u32.to_le_bytes().to_vec());
+        let copy = vec![accounts[0].clone(), source.info()];
+        assert_eq!(
+            process_instruction(&program_id, &copy, &[6]),
+            Err(HelloError::ConfirmationRequired.into())
+        );
+        // the counter can still move one step at a time
+        assert_eq!(process_instruction(&program_id, &accounts, &[1]), Ok(()));
+        assert_eq!(
+            read_counter(&accounts[0].data.borrow()),
+            Ok(SET_CONFIRM_THRESHOLD)
+        );
+    }
+
+    // A zeroed record, closed or made up front, names the default key as its
+    // proposer, passing that key doesn't make it a pending Set
+    #[test]
+    fn test_confirm_set_needs_a_pending_set() {
+        use crate::instruction::HelloInstruction;
+        use crate::seeds::PendingSetSeeds;
+        use crate::state::PendingSet;
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::greeting(&program_id);
+        let mut proposer = TestAccount::new(&Pubkey::default())
+            .key(Pubkey::default())
+            .writable();
+        let (pending_key, _) = PendingSetSeeds::new(&greeting.key).pda(&program_id);
+        let mut pending = TestAccount::new(&program_id)
+            .key(pending_key)
+            .data_len(PendingSet::LEN)
+            .writable();
+        let accounts = vec![greeting.info(), proposer.info(), pending.info()];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &HelloInstruction::ConfirmSet.pack()),
+            Err(ProgramError::UninitializedAccount)
+        );
+    }
+
+    #[test]
+    fn test_allowlisted_increment() {
+        use crate::instruction::HelloInstruction;
+        use crate::merkle;
+        use crate::seeds::AllowlistSeeds;
+        use crate::state::{read_counter, Allowlist};
+        use borsh::BorshSerialize;
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::greeting(&program_id);
+        let mut authority = TestAccount::wallet();
+        let mut member = TestAccount::wallet();
+        let mut outsider = TestAccount::wallet();
+        let members = vec![Pubkey::new_unique(), member.key, Pubkey::new_unique()];
+        let (allowlist_key, _) = AllowlistSeeds::new(&greeting.key).pda(&program_id);
+        // an allowlist created earlier by authority
+        let record = Allowlist {
+            greeting: greeting.key,
+            authority: authority.key,
+            root: merkle::root(&members),
+        }
+        .try_to_vec()
+        .unwrap();
+        let mut allowlist = TestAccount::new(&program_id)
+            .key(allowlist_key)
+            .data(record)
+            .writable();
+        let mut system_program = TestAccount::system_program();
+        let (greeting, allowlist, system_program) =
+            (greeting.info(), allowlist.info(), system_program.info());
+        let (authority, member, outsider) = (authority.info(), member.info(), outsider.info());
+        let mut memo = TestAccount::memo_sysvar();
+        let memo = memo.info();
+
+        let proof = merkle::proof(&members, 1).unwrap();
+        let increment = HelloInstruction::AllowlistedIncrement(proof).pack();
+        let accounts = with_memo(
+            vec![greeting.clone(), member.clone(), allowlist.clone()],
+            &memo,
+        );
+        process_instruction(&program_id, &accounts, &increment).unwrap();
+        assert_eq!(read_counter(&greeting.data.borrow()), Ok(1));
+
+        // the same proof doesn't work for another signer
+        let accounts = with_memo(
+            vec![greeting.clone(), outsider.clone(), allowlist.clone()],
+            &memo,
+        );
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &increment),
+            Err(HelloError::NotAllowlisted.into())
+        );
+
+        // only the authority replaces the root, after which the old proof
+        // is rejected too
+        let set = HelloInstruction::SetAllowlist([7; 32]).pack();
+        let accounts = vec![
+            greeting.clone(),
+            outsider.clone(),
+            allowlist.clone(),
+            system_program.clone(),
+        ];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &set),
+            Err(HelloError::Unauthorized.into())
+        );
+        let accounts = vec![
+            greeting.clone(),
+            authority.clone(),
+            allowlist.clone(),
+            system_program.clone(),
+        ];
+        process_instruction(&program_id, &accounts, &set).unwrap();
+        let accounts = with_memo(vec![greeting.clone(), member.clone(), allowlist], &memo);
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &increment),
+            Err(HelloError::NotAllowlisted.into())
+        );
+        assert_eq!(read_counter(&greeting.data.borrow()), Ok(1));
+
+        // pack() won't encode it
+        let mut too_long = vec![24, merkle::MAX_PROOF_LEN as u8 + 1];
+        too_long.resize(too_long.len() + (merkle::MAX_PROOF_LEN + 1) * 32, 0);
+        assert_eq!(
+            HelloInstruction::unpack(&too_long),
+            Err(HelloError::InvalidInstruction.into())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "a proof is at most 20 hashes")]
+    fn test_proof_over_max_len_panics() {
+        use crate::instruction::allowlisted_increment;
+        use crate::merkle::MAX_PROOF_LEN;
+
+        let key = Pubkey::new_unique();
+        let proof = vec![[0; 32]; MAX_PROOF_LEN + 1];
+        allowlisted_increment(&key, &key, &key, proof);
+    }
+
+    #[test]
+    fn test_signed_increment_counts_greeters() {
+        use crate::instruction::HelloInstruction;
+        use crate::seeds::GreeterStatsSeeds;
+        use crate::state::{read_counter, GreeterStats};
+        use borsh::BorshSerialize;
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::greeting(&program_id);
+        let (stats_key, _) = GreeterStatsSeeds::new(&greeting.key).pda(&program_id);
+        let record = GreeterStats::new(greeting.key).try_to_vec().unwrap();
+        let mut stats = TestAccount::new(&program_id)
+            .key(stats_key)
+            .data(record)
+            .writable();
+        let mut system_program = TestAccount::system_program();
+        let mut alice = TestAccount::wallet();
+        let mut bob = TestAccount::wallet();
+        let (greeting, stats, system_program) =
+            (greeting.info(), stats.info(), system_program.info());
+        let (alice, bob) = (alice.info(), bob.info());
+        let mut memo = TestAccount::memo_sysvar();
+        let memo = memo.info();
+
+        let signed_increment = HelloInstruction::SignedIncrement.pack();
+        for greeter in &[&alice, &bob, &alice] {
+            let accounts = with_memo(
+                vec![
+                    greeting.clone(),
+                    (*greeter).clone(),
+                    stats.clone(),
+                    system_program.clone(),
+                ],
+                &memo,
+            );
+            process_instruction(&program_id, &accounts, &signed_increment).unwrap();
+        }
+        assert_eq!(read_counter(&greeting.data.borrow()), Ok(3));
+        let recorded = GreeterStats::try_from_slice(&stats.data.borrow()).unwrap();
+        assert_eq!(recorded.unique_greeters_estimate, 2);
+        assert_eq!(recorded.unique_greeters_hll(), 2);
+
+        // the statistics of another greeting are refused
+        let mut other = TestAccount::greeting(&program_id);
+        let accounts = vec![other.info(), alice, stats, system_program];
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &signed_increment),
+            Err(ProgramError::InvalidSeeds)
+        );
+    }
+
+    #[cfg(feature = "anchor-discriminators")]
+    #[test]
+    fn test_anchor_discriminators() {
+        use crate::discriminator::to_anchor;
+        use crate::instruction::HelloInstruction;
+        use crate::state::read_counter;
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::greeting(&program_id);
+        let accounts = vec![greeting.info()];
+
+        let set = to_anchor(&HelloInstruction::Set(41).pack()).unwrap();
+        process_instruction(&program_id, &accounts, &set).unwrap();
+        let increment = to_anchor(&[0]).unwrap();
+        process_instruction(&program_id, &accounts, &increment).unwrap();
+        assert_eq!(read_counter(&accounts[0].data.borrow()), Ok(42));
+        // the tag keeps working next to it
+        process_instruction(&program_id, &accounts, &[1]).unwrap();
+        assert_eq!(read_counter(&accounts[0].data.borrow()), Ok(41));
+    }
+
+    // The fast path takes no heap allocations, logging included. The
+    // greeting and the metrics record are still logged when enabled. A
+    // `require-memo` build decodes the transaction's instructions to find
+    // the memo, which does allocate.
+    #[test]
+    fn test_increment_and_decrement_dont_allocate() {
+        use crate::instruction::HelloInstruction;
+        use crate::state::read_counter;
+        use crate::test_utils::count_allocations;
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::greeting(&program_id);
+        let mut memo = TestAccount::memo_sysvar();
+        let memo = memo.info();
+        let accounts = with_memo(vec![greeting.info()], &memo);
+        let increment = HelloInstruction::Increment.pack();
+        let decrement = HelloInstruction::Decrement.pack();
+
+        for data in &[&increment, &increment, &decrement] {
+            let (result, allocations, logged) =
+                count_allocations(|| process_instruction(&program_id, &accounts, data));
+            assert_eq!(result, Ok(()));
+            if !cfg!(feature = "require-memo") {
+                assert_eq!(allocations, 0);
+            }
+            if cfg!(any(feature = "log-info", feature = "metrics")) {
+                assert!(logged >= 1);
+            }
+        }
+        assert_eq!(read_counter(&accounts[0].data.borrow()), Ok(1));
+    }
+
+    // The same through the entrypoint, over the input the loader passes.
+    // deserialize() allocates the AccountInfos, nothing after it does.
+    #[cfg(not(feature = "no-entrypoint"))]
+    #[test]
+    fn test_entrypoint_allocates_only_to_deserialize() {
+        use crate::instruction::HelloInstruction;
+        use crate::state::read_counter;
+        use crate::test_utils::{count_allocations, serialize_input};
+        use solana_program::entrypoint::{deserialize, SUCCESS};
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::greeting(&program_id);
+        let mut memo = TestAccount::memo_sysvar();
+        let memo = memo.info();
+        let accounts = with_memo(vec![greeting.info()], &memo);
+        let increment = HelloInstruction::Increment.pack();
+        let mut buffer = serialize_input(&program_id, &accounts, &increment);
+        let input = buffer.as_mut_ptr() as *mut u8;
+
+        let (_, deserialized, _) = count_allocations(|| unsafe { deserialize(input) });
+        assert!(deserialized > 0);
+        let (result, allocations, _) = count_allocations(|| unsafe { entrypoint(input) });
+        assert_eq!(result, SUCCESS);
+        if !cfg!(feature = "require-memo") {
+            assert_eq!(allocations, deserialized);
+        }
+        let (_, accounts, _) = unsafe { deserialize(input) };
+        assert_eq!(read_counter(&accounts[0].data.borrow()), Ok(1));
+    }
+
+    #[test]
+    fn test_idempotent_ops() {
+        use crate::instruction::HelloInstruction;
+        use crate::seeds::RecentOpsSeeds;
+        use crate::state::{read_counter, RecentOps, RECENT_OPS_LEN};
+        use borsh::BorshSerialize;
+
+        let program_id = Pubkey::new_unique();
+        let mut greeting = TestAccount::greeting(&program_id);
+        let mut payer = TestAccount::wallet();
+        let (window_key, _) = RecentOpsSeeds::new(&greeting.key).pda(&program_id);
+        // a window created by an earlier Idempotent
+        let mut window = TestAccount::new(&program_id)
+            .key(window_key)
+            .data(RecentOps::new(greeting.key).try_to_vec().unwrap())
+            .writable();
+        let mut system_program = TestAccount::system_program();
+        let mut memo = TestAccount::memo_sysvar();
+        let memo = memo.info();
+        let accounts = with_memo(
+            vec![
+                greeting.info(),
+                payer.info(),
+                window.info(),
+                system_program.info(),
+            ],
+            &memo,
+        );
+        let op = |id: u8, op| HelloInstruction::Idempotent([id; 16], Box::new(op)).pack();
+
+        // a retry of the same op_id is rejected, a new op_id goes through
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &op(1, HelloInstruction::Increment)),
+            Ok(())
+        );
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &op(1, HelloInstruction::Increment)),
+            Err(HelloError::DuplicateOperation.into())
+        );
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &op(2, HelloInstruction::Set(5))),
+            Ok(())
+        );
+        assert_eq!(read_counter(&accounts[0].data.borrow()), Ok(5));
+
+        // once RECENT_OPS_LEN newer ops have been recorded op_id 1 is forgotten
+        for id in 3..=(RECENT_OPS_LEN as u8 + 1) {
+            process_instruction(&program_id, &accounts, &op(id, HelloInstruction::Increment))
+                .unwrap();
+        }
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &op(2, HelloInstruction::Increment)),
+            Err(HelloError::DuplicateOperation.into())
+        );
+        assert_eq!(
+            process_instruction(&program_id, &accounts, &op(1, HelloInstruction::Increment)),
+            Ok(())
+        );
+
+        // only the counter ops can be wrapped, and zero is the empty slot
+        assert_eq!(
+            HelloInstruction::unpack(&op(3, HelloInstruction::GetVersion)),
+            Err(HelloError::InvalidInstruction.into())
+        );
+        assert_eq!(
+            HelloInstruction::unpack(&op(0, HelloInstruction::Increment)),
+            Err(HelloError::InvalidInstruction.into())
+        );
+    }
 }
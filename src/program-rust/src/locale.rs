@@ -0,0 +1,33 @@
+// Languages of the "Greeted N time(s)" log. A greeting's language is the
+// two letter ISO 639-1 code kept in its GreetingMetadata record, set with
+// SetLanguage. The table is compiled into the program, adding a language
+// means adding an entry to SUPPORTED and an arm to log_greeted().
+//
+// Only Increment, Decrement and Set are localized, as they are the
+// instructions that take the optional metadata record (see GreetingContext).
+// Every other instruction that changes a counter, MirrorIncrement, the
+// votes, Idempotent, CheckIn, ConfirmSet, AllowlistedIncrement and
+// SignedIncrement, logs in English whatever the greeting's language. So do
+// the ops of a Batch, which don't pass the record on.
+
+/// Language used when a greeting has no metadata record
+pub const DEFAULT_LANGUAGE: [u8; 2] = *b"en";
+
+/// Every language SetLanguage accepts
+pub const SUPPORTED: [[u8; 2]; 5] = [*b"en", *b"es", *b"fr", *b"de", *b"pt"];
+
+pub fn is_supported(language: &[u8; 2]) -> bool {
+    SUPPORTED.contains(language)
+}
+
+/// Log the new counter value of a greeting in `language`, for the
+/// instructions listed at the top of this file
+pub fn log_greeted(language: &[u8; 2], counter: u32) {
+    match language {
+        b"es" => log_info!("¡Saludado {} vez/veces!", counter),
+        b"fr" => log_info!("Salué {} fois !", counter),
+        b"de" => log_info!("{} Mal gegrüßt!", counter),
+        b"pt" => log_info!("Saudado {} vez(es)!", counter),
+        _ => log_info!("Greeted {} time(s)!", counter),
+    }
+}
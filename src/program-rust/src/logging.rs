@@ -0,0 +1,213 @@
+//src/program-rust/src/logging.rs
+// Logging macros that can be compiled out of production builds.
+//
+// Every msg! call costs compute units, so the chatty log lines go through
+// these wrappers instead:
+//   log_info!  - logged when the `log-info` feature is enabled (on by default)
+//   log_debug! - logged when the `log-debug` feature is enabled
+//
+// Build with `--no-default-features` to strip both. The arguments are still
+// type checked when a level is disabled, but the `if` is on a constant so the
+// compiler drops the whole call from the binary.
+//
+// Unlike msg!, which formats into a String, both format the line into a
+// buffer on the stack, so logging doesn't cost Increment and Decrement a
+// heap allocation. Lines longer than LOG_LINE_LEN bytes are cut short.
+//
+// The `diagnostics` feature turns on the tracing and error context below,
+// for debugging from transaction logs.
+
+use solana_program::program_error::ProgramError;
+use std::fmt::{self, Display};
+
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "log-info") {
+            $crate::logging::log_line(format_args!($($arg)*));
+        }
+    };
+}
+
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        if cfg!(feature = "log-debug") {
+            $crate::logging::log_line(format_args!($($arg)*));
+        }
+    };
+}
+
+/// Longest line log_info! and log_debug! log, in bytes
+pub const LOG_LINE_LEN: usize = 256;
+
+struct Line {
+    bytes: [u8; LOG_LINE_LEN],
+    len: usize,
+}
+
+impl fmt::Write for Line {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // whole characters only, so the line stays valid UTF-8
+        let mut take = s.len().min(LOG_LINE_LEN - self.len);
+        while !s.is_char_boundary(take) {
+            take -= 1;
+        }
+        self.bytes[self.len..self.len + take].copy_from_slice(&s.as_bytes()[..take]);
+        self.len += take;
+        Ok(())
+    }
+}
+
+/// Log `args` without allocating, see log_info!
+pub fn log_line(args: fmt::Arguments) {
+    let mut line = Line {
+        bytes: [0; LOG_LINE_LEN],
+        len: 0,
+    };
+    // writing to a Line never fails, it only stops taking bytes
+    let _ = fmt::write(&mut line, args);
+    solana_program::log::sol_log(std::str::from_utf8(&line.bytes[..line.len]).unwrap_or_default());
+}
+
+// enter! and exit! trace where a handler is, in builds with the
+// `diagnostics` feature:
+//
+//   let span = enter!("claim_badge");
+//   let cpi = enter!(span, "create badge account");
+//   pda::create_pda_account(...)?;
+//   exit!(cpi);
+//
+// logs
+//
+//   trace: > claim_badge
+//   trace:   > create badge account
+//   trace:   < create badge account
+//   trace: < claim_badge
+//
+// with the runtime's own invoke and success lines of the CPI in between. A
+// span also exits when it is dropped, so returning early with `?` still
+// closes it. Without the feature a Span logs nothing.
+
+macro_rules! enter {
+    ($name:expr) => {
+        $crate::logging::Span::enter($name, 0)
+    };
+    ($parent:expr, $name:expr) => {
+        $crate::logging::Span::enter($name, $parent.depth() + 1)
+    };
+}
+
+macro_rules! exit {
+    ($span:expr) => {
+        drop($span)
+    };
+}
+
+/// A traced section of a handler, see enter!
+pub struct Span {
+    name: &'static str,
+    depth: usize,
+}
+
+impl Span {
+    /// Log the entry of `name`, `depth` levels in. Use enter! instead.
+    pub fn enter(name: &'static str, depth: usize) -> Self {
+        if cfg!(feature = "diagnostics") {
+            log_line(format_args!(
+                "trace: {:indent$}> {}",
+                "",
+                name,
+                indent = depth * 2
+            ));
+        }
+        Self { name, depth }
+    }
+
+    /// How deep the span is nested, 0 for a handler
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if cfg!(feature = "diagnostics") {
+            log_line(format_args!(
+                "trace: {:indent$}< {}",
+                "",
+                self.name,
+                indent = self.depth * 2
+            ));
+        }
+    }
+}
+
+// with_context() says why an error is being returned, in builds with the
+// `diagnostics` feature:
+//
+//   Err(ProgramError::InvalidSeeds).with_context("receipt address", 2, expected, receipt.key)
+//
+// logs "receipt address check failed on account 2: expected <pda>, got <key>"
+// ahead of the error, so an explorer shows which account to fix and what it
+// should have been. Without the feature nothing is formatted and the Result
+// is passed through as is. The plain msg! lines the checks already log are
+// kept either way.
+
+pub trait WithContext<T> {
+    /// On error, log `check`, the index of the account it was run on and
+    /// the expected and actual values, then return the error
+    fn with_context(
+        self,
+        check: &str,
+        account: usize,
+        expected: impl Display,
+        actual: impl Display,
+    ) -> Result<T, ProgramError>;
+}
+
+impl<T, E: Into<ProgramError>> WithContext<T> for Result<T, E> {
+    fn with_context(
+        self,
+        check: &str,
+        account: usize,
+        expected: impl Display,
+        actual: impl Display,
+    ) -> Result<T, ProgramError> {
+        self.map_err(|err| {
+            let err = err.into();
+            if cfg!(feature = "diagnostics") {
+                log_line(format_args!(
+                    "{} check failed on account {}: expected {}, got {} ({})",
+                    check, account, expected, actual, err
+                ));
+            }
+            err
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::HelloError;
+
+    #[test]
+    fn test_spans_nest() {
+        let span = enter!("claim_badge");
+        let cpi = enter!(span, "create badge account");
+        let inner = enter!(cpi, "transfer");
+        assert_eq!((span.depth(), cpi.depth(), inner.depth()), (0, 1, 2));
+        exit!(inner);
+        exit!(cpi);
+    }
+
+    #[test]
+    fn test_with_context_keeps_the_result() {
+        let ok: Result<u32, ProgramError> = Ok(7);
+        assert_eq!(ok.with_context("counter", 0, 1, 2), Ok(7));
+        let err: Result<u32, HelloError> = Err(HelloError::CounterOverflow);
+        assert_eq!(
+            err.with_context("counter overflow", 0, "below u32::MAX", u32::MAX),
+            Err(HelloError::CounterOverflow.into())
+        );
+    }
+}
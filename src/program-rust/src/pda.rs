@@ -0,0 +1,86 @@
+//src/program-rust/src/pda.rs
+// Program derived addresses used by the program and the helpers that
+// create accounts at them. The seeds themselves are in seeds.rs.
+
+use crate::seeds::{AttestationSeeds, MetadataSeeds, ReceiptSeeds};
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult,
+    program::invoke_signed,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+// so existing pda::RECEIPT_SEED style paths keep working
+pub use crate::seeds::{ATTESTATION_SEED, METADATA_SEED, RECEIPT_SEED};
+
+/// Address of the receipt recording that `voter` voted on `target`.
+/// There is exactly one per (target, voter) pair, which is what limits
+/// every wallet to a single vote.
+pub fn find_receipt_address(program_id: &Pubkey, target: &Pubkey, voter: &Pubkey) -> (Pubkey, u8) {
+    ReceiptSeeds::new(target, voter).pda(program_id)
+}
+
+/// Address of the attestation record of `greeting`. Each greeting has one,
+/// which every Attest overwrites.
+pub fn find_attestation_address(program_id: &Pubkey, greeting: &Pubkey) -> (Pubkey, u8) {
+    AttestationSeeds::new(greeting).pda(program_id)
+}
+
+/// Address of the metadata record of `greeting`
+pub fn find_metadata_address(program_id: &Pubkey, greeting: &Pubkey) -> (Pubkey, u8) {
+    MetadataSeeds::new(greeting).pda(program_id)
+}
+
+/// Create a rent exempt account of `space` bytes owned by `owner` at the
+/// PDA signed for by `seeds` (which must include the bump).
+///
+/// Anyone can send lamports to an address before the program creates an
+/// account there, and create_account refuses to use an address with a
+/// balance. In that case only the missing rent is transferred and the
+/// account is allocated and assigned in separate steps instead.
+pub fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    new_account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    owner: &Pubkey,
+    space: usize,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let required = Rent::get()?.minimum_balance(space);
+    let current = new_account.lamports();
+
+    if current == 0 {
+        return invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                new_account.key,
+                required,
+                space as u64,
+                owner,
+            ),
+            &[payer.clone(), new_account.clone(), system_program.clone()],
+            &[seeds],
+        );
+    }
+
+    if current < required {
+        invoke_signed(
+            &system_instruction::transfer(payer.key, new_account.key, required - current),
+            &[payer.clone(), new_account.clone(), system_program.clone()],
+            &[],
+        )?;
+    }
+    invoke_signed(
+        &system_instruction::allocate(new_account.key, space as u64),
+        &[new_account.clone(), system_program.clone()],
+        &[seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(new_account.key, owner),
+        &[new_account.clone(), system_program.clone()],
+        &[seeds],
+    )
+}
@@ -0,0 +1,1049 @@
+//src/program-rust/src/processor.rs
+// Instruction processing: decode the instruction and run its handler
+
+use crate::accounts::{
+    AllowlistContext, AllowlistedIncrementContext, AttestContext, BadgeContext, BatchContext,
+    CancelSetContext, CheckInContext, ConfirmSetContext, CopyFromContext, DepositContext,
+    GetMultipleContext, GreetingContext, IdempotentContext, MetadataContext, MirrorContext,
+    PollContext, ProgramDataContext, ProposeSetContext, SignedIncrementContext, VoteContext,
+    WithdrawContext,
+};
+use crate::config;
+#[cfg(feature = "anchor-discriminators")]
+use crate::discriminator;
+use crate::error::HelloError;
+use crate::instruction::{self, HelloInstruction};
+#[cfg(feature = "require-memo")]
+use crate::introspection;
+use crate::locale;
+use crate::logging::{Span, WithContext};
+use crate::merkle;
+use crate::metrics::{MetricsRecord, METRICS_TAG};
+use crate::pda;
+use crate::seeds::{
+    AllowlistSeeds, AttestationSeeds, BadgeSeeds, GreeterStatsSeeds, MetadataSeeds,
+    PendingSetSeeds, ReceiptSeeds, RecentOpsSeeds, StreakSeeds, VaultSeeds,
+};
+use crate::state::{
+    read_counter, write_counter, Allowlist, Attestation, Badge, GreeterStats, GreetingAccount,
+    GreetingMetadata, PendingSet, Poll, PollOption, RecentOps, Streak, Vault, VersionInfo,
+    VoteReceipt, SET_CONFIRM_THRESHOLD,
+};
+use crate::upgrade;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::ProgramResult,
+    log::sol_log_data,
+    msg,
+    program::{invoke, set_return_data},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction,
+    sysvar::Sysvar,
+};
+
+// Program entrypoint's implementation
+pub fn process_instruction(
+    program_id: &Pubkey, // Public key of the account the hello world program was loaded into
+    accounts: &[AccountInfo], // The account to say hello to
+    instruction_data: &[u8], 
+) -> ProgramResult {
+    log_debug!("Hello World Rust program entrypoint");
+
+    // from here on Anchor style data looks like it came with the tag, see
+    // hello_types::discriminator for how the two are told apart
+    #[cfg(feature = "anchor-discriminators")]
+    let translated = match discriminator::detect(instruction_data) {
+        discriminator::DataFormat::Anchor => {
+            log_debug!("Anchor style instruction data");
+            discriminator::from_anchor(instruction_data)
+        }
+        discriminator::DataFormat::Tag => None,
+    };
+    #[cfg(feature = "anchor-discriminators")]
+    let instruction_data = translated.as_deref().unwrap_or(instruction_data);
+
+    let result = dispatch(program_id, accounts, instruction_data);
+    // logged for failed instructions too, a failing transaction keeps its logs
+    if cfg!(feature = "metrics") {
+        log_metrics(accounts.len(), instruction_data, &result);
+    }
+    result
+}
+
+fn dispatch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    // Send the instrcion_data obtained from client to the unpack function
+    // to decode data to HelloInstruction enum
+    let instruction = HelloInstruction::unpack(instruction_data)?;
+
+    // instruction is an HelloInstruction enum already desctructured from the Result
+    // Check what the instruction value unpacked to and hand it to the
+    // handler for that variant
+    match instruction {
+        HelloInstruction::Increment => process_increment(program_id, accounts),
+        HelloInstruction::Decrement => process_decrement(program_id, accounts),
+        HelloInstruction::Set(value) => process_set(program_id, accounts, value),
+        HelloInstruction::GetVersion => process_get_version(accounts),
+        HelloInstruction::Batch(ops) => process_batch(program_id, accounts, ops),
+        HelloInstruction::MirrorIncrement => process_mirror_increment(program_id, accounts),
+        HelloInstruction::CopyFrom => process_copy_from(program_id, accounts),
+        HelloInstruction::Upvote => process_vote(program_id, accounts, VoteReceipt::UP),
+        HelloInstruction::Downvote => process_vote(program_id, accounts, VoteReceipt::DOWN),
+        HelloInstruction::CreatePoll(labels) => process_create_poll(program_id, accounts, labels),
+        HelloInstruction::Vote(index) => process_poll_vote(program_id, accounts, index),
+        HelloInstruction::ClosePoll => process_close_poll(program_id, accounts),
+        HelloInstruction::Attest(hash) => process_attest(program_id, accounts, hash),
+        HelloInstruction::SetUri(uri) => process_set_uri(program_id, accounts, uri),
+        HelloInstruction::SetLanguage(language) => {
+            process_set_language(program_id, accounts, language)
+        }
+        HelloInstruction::Idempotent(op_id, op) => {
+            process_idempotent(program_id, accounts, op_id, *op)
+        }
+        HelloInstruction::Deposit(amount, target) => {
+            process_deposit(program_id, accounts, amount, target)
+        }
+        HelloInstruction::Withdraw => process_withdraw(program_id, accounts),
+        HelloInstruction::ClaimBadge(milestone) => {
+            process_claim_badge(program_id, accounts, milestone)
+        }
+        HelloInstruction::CheckIn => process_check_in(program_id, accounts),
+        HelloInstruction::CheckUpgradeAuthority(strict) => {
+            process_check_upgrade_authority(program_id, accounts, strict)
+        }
+        HelloInstruction::ProposeSet(value) => process_propose_set(program_id, accounts, value),
+        HelloInstruction::ConfirmSet => process_confirm_set(program_id, accounts),
+        HelloInstruction::SetAllowlist(root) => process_set_allowlist(program_id, accounts, root),
+        HelloInstruction::AllowlistedIncrement(proof) => {
+            process_allowlisted_increment(program_id, accounts, proof)
+        }
+        HelloInstruction::SignedIncrement => process_signed_increment(program_id, accounts),
+        HelloInstruction::GetMultiple => process_get_multiple(program_id, accounts),
+        HelloInstruction::CancelSet => process_cancel_set(program_id, accounts),
+    }
+}
+
+// The slot is 0 where there is no clock, in off-chain unit tests
+fn log_metrics(accounts: usize, instruction_data: &[u8], result: &ProgramResult) {
+    let slot = Clock::get().map(|clock| clock.slot).unwrap_or(0);
+    let result = match result {
+        Ok(()) => 0,
+        Err(e) => u64::from(e.clone()),
+    };
+    let record = MetricsRecord::new(instruction_data, accounts, slot, result);
+    // on the stack, so Increment and Decrement stay allocation free
+    let mut bytes = [0; MetricsRecord::LEN];
+    if record.serialize(&mut &mut bytes[..]).is_ok() {
+        sol_log_data(&[METRICS_TAG, &bytes]);
+    }
+}
+
+// Increment and Decrement only touch the counter, so they take the fast
+// path: the 4 counter bytes are read and written in place at their fixed
+// offset instead of doing a full Borsh round trip of the struct. Without a
+// metadata record nothing on the way allocates either, logging and metrics
+// included, see test_increment_and_decrement_dont_allocate.
+fn process_increment(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let _span = enter!("increment");
+    // Check the accounts passed in match what the instruction expects, in
+    // number and in order, before touching any data
+    let ctx = GreetingContext::load(program_id, accounts)?;
+    let counter = increment_counter(ctx.greeting, ctx.instructions)?;
+
+    locale::log_greeted(&greeting_language(program_id, &ctx)?, counter);
+    Ok(())
+}
+
+// Every instruction that adds to a counter goes through here, so a
+// `require-memo` build checks for the memo in this one place. `instructions`
+// is the optional instructions sysvar of the instruction's accounts.
+fn increment_counter(
+    greeting: &AccountInfo,
+    instructions: Option<&AccountInfo>,
+) -> Result<u32, ProgramError> {
+    #[cfg(feature = "require-memo")]
+    introspection::require_memo(instructions.ok_or(ProgramError::NotEnoughAccountKeys)?)?;
+    #[cfg(not(feature = "require-memo"))]
+    let _ = instructions;
+    let mut data = greeting.data.borrow_mut();
+    let counter =
+        read_counter(&data).with_context("greeting length", 0, GreetingAccount::LEN, data.len())?;
+    let counter = counter
+        .checked_add(1)
+        .ok_or(HelloError::CounterOverflow)
+        .with_context("counter overflow", 0, "below u32::MAX", counter)?;
+    write_counter(&mut data, counter)?;
+    Ok(counter)
+}
+
+fn process_decrement(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let _span = enter!("decrement");
+    let ctx = GreetingContext::load(program_id, accounts)?;
+    let counter = decrement_counter(ctx.greeting)?;
+
+    locale::log_greeted(&greeting_language(program_id, &ctx)?, counter);
+    Ok(())
+}
+
+fn process_set(program_id: &Pubkey, accounts: &[AccountInfo], value: u32) -> ProgramResult {
+    let _span = enter!("set");
+    let ctx = GreetingContext::load(program_id, accounts)?;
+    // this variable should have been called account_info instead as that is what
+    // we are getting back
+    let account = ctx.greeting;
+
+    // de-serialize using the try_from_slice() function the reference to [u8]
+    // in the account.data
+    // we get an instance of the struct GreetingAccount. we save it as a mutable
+    // variable to change the field counter of the struct's instance
+    let mut greeting_account = GreetingAccount::try_from_slice(&account.data.borrow())?;
+    check_direct_set(greeting_account.counter)?;
+    greeting_account.counter = value;
+
+    // storing the data as bytes by serializing it
+    greeting_account.serialize(&mut &mut account.data.borrow_mut()[..])?;
+
+    locale::log_greeted(
+        &greeting_language(program_id, &ctx)?,
+        greeting_account.counter,
+    );
+    Ok(())
+}
+
+// Set, including inside Batch and Idempotent, and CopyFrom can only
+// overwrite counters up to SET_CONFIRM_THRESHOLD in one go
+fn check_direct_set(counter: u32) -> ProgramResult {
+    if counter > SET_CONFIRM_THRESHOLD {
+        msg!(
+            "Counter is above {}, use ProposeSet and ConfirmSet",
+            SET_CONFIRM_THRESHOLD
+        );
+        return Err(HelloError::ConfirmationRequired.into());
+    }
+    Ok(())
+}
+
+// The language of the greeting's metadata record if the caller passed it
+// and SetUri/SetLanguage created it, the default otherwise
+fn greeting_language(program_id: &Pubkey, ctx: &GreetingContext) -> Result<[u8; 2], ProgramError> {
+    match ctx.metadata {
+        Some(metadata) if metadata.owner == program_id => {
+            Ok(GreetingMetadata::unpack(&metadata.data.borrow())?.language)
+        }
+        _ => Ok(locale::DEFAULT_LANGUAGE),
+    }
+}
+
+// GetVersion takes no accounts and returns the Borsh encoded VersionInfo of
+// this build, so a client can simulate it to see what is deployed
+fn process_get_version(accounts: &[AccountInfo]) -> ProgramResult {
+    let _span = enter!("get_version");
+    if !accounts.is_empty() {
+        msg!("GetVersion takes no accounts");
+        return Err(HelloError::TooManyAccounts.into());
+    }
+    let version = VersionInfo::new(env!("CARGO_PKG_VERSION"));
+    set_return_data(&version.try_to_vec()?);
+
+    log_info!("Version {} (layout {})", version.version, version.layout_version);
+    Ok(())
+}
+
+// GetMultiple returns the counters of the greetings passed, in order, as a
+// Borsh encoded Vec<u32>, for CPI callers and simulations reading several at
+// once
+fn process_get_multiple(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let _span = enter!("get_multiple");
+    let ctx = GetMultipleContext::load(program_id, accounts)?;
+    let mut counters = Vec::with_capacity(ctx.greetings.len());
+    for (index, greeting) in ctx.greetings.iter().enumerate() {
+        let data = greeting.data.borrow();
+        let counter = read_counter(&data).with_context(
+            "greeting length",
+            index,
+            GreetingAccount::LEN,
+            data.len(),
+        )?;
+        counters.push(counter);
+    }
+    set_return_data(&counters.try_to_vec()?);
+
+    log_info!("Read {} counters", counters.len());
+    Ok(())
+}
+
+fn decrement_counter(greeting: &AccountInfo) -> Result<u32, ProgramError> {
+    let mut data = greeting.data.borrow_mut();
+    let counter =
+        read_counter(&data).with_context("greeting length", 0, GreetingAccount::LEN, data.len())?;
+    let counter = counter
+        .checked_sub(1)
+        .ok_or(HelloError::CounterUnderflow)
+        .with_context("counter underflow", 0, "above 0", counter)?;
+    write_counter(&mut data, counter)?;
+    Ok(counter)
+}
+
+// Batch re-encodes each op and invokes this same program with it. Direct
+// self-recursion is the one kind of reentrancy the runtime allows, and every
+// op runs through the normal handler with all of its checks. The instructions
+// sysvar goes along when it was passed, for the memo check of Increment.
+fn process_batch(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    ops: Vec<HelloInstruction>,
+) -> ProgramResult {
+    let span = enter!("batch");
+    let ctx = BatchContext::load(program_id, accounts)?;
+
+    log_info!("Running batch of {} operation(s)", ops.len());
+    for op in ops {
+        let ix = match op {
+            HelloInstruction::Increment => instruction::increment(program_id, ctx.greeting.key),
+            HelloInstruction::Decrement => instruction::decrement(program_id, ctx.greeting.key),
+            HelloInstruction::Set(value) => instruction::set(program_id, ctx.greeting.key, value),
+            // unpack() never puts anything else in a Batch
+            _ => return Err(HelloError::InvalidInstruction.into()),
+        };
+        let cpi = enter!(span, "batch op");
+        match ctx.instructions {
+            Some(instructions) => invoke(
+                &instruction::with_instructions_sysvar(ix),
+                &[
+                    ctx.greeting.clone(),
+                    instructions.clone(),
+                    ctx.program.clone(),
+                ],
+            )?,
+            None => invoke(&ix, &[ctx.greeting.clone(), ctx.program.clone()])?,
+        }
+        exit!(cpi);
+    }
+    Ok(())
+}
+
+// Update the local counter first, then ask the mirror deployment to do the
+// same with its own account. The local borrow ends inside
+// increment_counter(), before the CPI, so the callee is free to borrow the
+// accounts it was handed.
+fn process_mirror_increment(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let span = enter!("mirror_increment");
+    let ctx = MirrorContext::load(program_id, accounts)?;
+    let counter = increment_counter(ctx.greeting, ctx.instructions)?;
+    log_info!("Greeted {} time(s)!", counter);
+
+    let _cpi = enter!(span, "mirror increment");
+    let ix = instruction::increment(ctx.mirror_program.key, ctx.mirror_greeting.key);
+    match ctx.instructions {
+        Some(instructions) => invoke(
+            &instruction::with_instructions_sysvar(ix),
+            &[
+                ctx.mirror_greeting.clone(),
+                instructions.clone(),
+                ctx.mirror_program.clone(),
+            ],
+        ),
+        None => invoke(
+            &ix,
+            &[ctx.mirror_greeting.clone(), ctx.mirror_program.clone()],
+        ),
+    }
+}
+
+// The source is only ever borrowed for reading. Its length is checked
+// before decoding, so a foreign account with a different layout is
+// rejected instead of being decoded as whatever its first bytes happen to be.
+fn process_copy_from(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let _span = enter!("copy_from");
+    let ctx = CopyFromContext::load(program_id, accounts)?;
+
+    let source_data = ctx.source.data.borrow();
+    if source_data.len() != GreetingAccount::LEN {
+        msg!("Source account is not a GreetingAccount");
+        return Err(ProgramError::InvalidAccountData).with_context(
+            "source length",
+            1,
+            GreetingAccount::LEN,
+            source_data.len(),
+        );
+    }
+    let source = GreetingAccount::try_from_slice(&source_data)?;
+
+    let mut data = ctx.greeting.data.borrow_mut();
+    check_direct_set(read_counter(&data)?)?;
+    write_counter(&mut data, source.counter)?;
+
+    log_info!("Copied {} from {}", source.counter, ctx.source.key);
+    Ok(())
+}
+
+// A receipt PDA owned by this program means the voter has already voted.
+// Creating it is what records the vote, so the check and the record can't
+// get out of step.
+fn process_vote(program_id: &Pubkey, accounts: &[AccountInfo], choice: u8) -> ProgramResult {
+    let span = enter!("vote");
+    let ctx = VoteContext::load(program_id, accounts)?;
+    record_vote(program_id, &ctx, choice, &span)?;
+
+    let counter = if choice == VoteReceipt::UP {
+        increment_counter(ctx.target, ctx.instructions)?
+    } else {
+        decrement_counter(ctx.target)?
+    };
+    log_info!("Greeted {} time(s)!", counter);
+    Ok(())
+}
+
+// Create the voter's receipt for ctx.target, or fail if there already is one
+fn record_vote(program_id: &Pubkey, ctx: &VoteContext, choice: u8, span: &Span) -> ProgramResult {
+    if ctx.receipt.owner == program_id {
+        msg!("{} has already voted on {}", ctx.voter.key, ctx.target.key);
+        return Err(HelloError::AlreadyVoted.into());
+    }
+
+    let cpi = enter!(span, "create receipt account");
+    pda::create_pda_account(
+        ctx.voter,
+        ctx.receipt,
+        ctx.system_program,
+        program_id,
+        VoteReceipt::LEN,
+        &ReceiptSeeds::new(ctx.target.key, ctx.voter.key).with_bump(&[ctx.receipt_bump]),
+    )?;
+    exit!(cpi);
+    VoteReceipt {
+        target: *ctx.target.key,
+        voter: *ctx.voter.key,
+        choice,
+    }
+    .serialize(&mut &mut ctx.receipt.data.borrow_mut()[..])
+    .map_err(|e| e.into())
+}
+
+// The poll account is created by the client, like greeting accounts, with
+// at least Poll::LEN zeroed bytes. CreatePoll fills it in once.
+fn process_create_poll(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    labels: Vec<String>,
+) -> ProgramResult {
+    let _span = enter!("create_poll");
+    let ctx = PollContext::load(program_id, accounts)?;
+    let mut data = ctx.poll.data.borrow_mut();
+    if data.len() < Poll::LEN {
+        msg!("Poll account needs at least {} bytes", Poll::LEN);
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    if Poll::unpack(&data)?.is_initialized {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let poll = Poll {
+        is_initialized: true,
+        authority: *ctx.authority.key,
+        is_open: true,
+        options: labels
+            .into_iter()
+            .map(|label| PollOption { label, count: 0 })
+            .collect(),
+    };
+    poll.serialize(&mut &mut data[..])?;
+
+    log_info!("Created poll with {} option(s)", poll.options.len());
+    Ok(())
+}
+
+// Votes on a poll are deduplicated with the same receipt PDAs as
+// Upvote/Downvote, with the option index as the receipt's choice
+fn process_poll_vote(program_id: &Pubkey, accounts: &[AccountInfo], index: u8) -> ProgramResult {
+    let span = enter!("poll_vote");
+    let ctx = VoteContext::load(program_id, accounts)?;
+    let mut poll = Poll::unpack_initialized(&ctx.target.data.borrow())?;
+    if !poll.is_open {
+        return Err(HelloError::PollClosed.into());
+    }
+    let option = poll
+        .options
+        .get_mut(index as usize)
+        .ok_or(HelloError::InvalidPollOption)?;
+    option.count = option
+        .count
+        .checked_add(1)
+        .ok_or(HelloError::CounterOverflow)?;
+
+    record_vote(program_id, &ctx, index, &span)?;
+    poll.serialize(&mut &mut ctx.target.data.borrow_mut()[..])?;
+
+    log_info!("Voted for option {}", index);
+    Ok(())
+}
+
+// Closing stops new votes, the counts stay readable in the account
+fn process_close_poll(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let _span = enter!("close_poll");
+    let ctx = PollContext::load(program_id, accounts)?;
+    let mut poll = Poll::unpack_initialized(&ctx.poll.data.borrow())?;
+    if poll.authority != *ctx.authority.key {
+        msg!("Only the poll authority can close the poll");
+        return Err(HelloError::Unauthorized).with_context(
+            "poll authority",
+            1,
+            poll.authority,
+            ctx.authority.key,
+        );
+    }
+    poll.is_open = false;
+    poll.serialize(&mut &mut ctx.poll.data.borrow_mut()[..])?;
+
+    log_info!("Closed poll");
+    Ok(())
+}
+
+// The first Attest on a greeting creates its record, later ones overwrite
+// it, so the record always holds the latest hash
+fn process_attest(program_id: &Pubkey, accounts: &[AccountInfo], hash: [u8; 32]) -> ProgramResult {
+    let span = enter!("attest");
+    let ctx = AttestContext::load(program_id, accounts)?;
+    let slot = Clock::get()?.slot;
+
+    if ctx.attestation.owner != program_id {
+        let cpi = enter!(span, "create attestation account");
+        pda::create_pda_account(
+            ctx.attester,
+            ctx.attestation,
+            ctx.system_program,
+            program_id,
+            Attestation::LEN,
+            &AttestationSeeds::new(ctx.greeting.key).with_bump(&[ctx.attestation_bump]),
+        )?;
+        exit!(cpi);
+    }
+    Attestation {
+        greeting: *ctx.greeting.key,
+        hash,
+        slot,
+        attester: *ctx.attester.key,
+    }
+    .serialize(&mut &mut ctx.attestation.data.borrow_mut()[..])?;
+
+    log_info!("Attested {} at slot {}", ctx.greeting.key, slot);
+    Ok(())
+}
+
+// The first SetUri or SetLanguage on a greeting creates its metadata record
+// and makes the signer its authority, later ones have to be signed by that
+// authority. Returns the record to update.
+fn load_or_create_metadata(
+    program_id: &Pubkey,
+    ctx: &MetadataContext,
+    span: &Span,
+) -> Result<GreetingMetadata, ProgramError> {
+    if ctx.metadata.owner == program_id {
+        let metadata = GreetingMetadata::unpack(&ctx.metadata.data.borrow())?;
+        if metadata.authority != *ctx.authority.key {
+            msg!("Only the metadata authority can change the metadata");
+            return Err(HelloError::Unauthorized).with_context(
+                "metadata authority",
+                1,
+                metadata.authority,
+                ctx.authority.key,
+            );
+        }
+        return Ok(metadata);
+    }
+
+    let cpi = enter!(span, "create metadata account");
+    pda::create_pda_account(
+        ctx.authority,
+        ctx.metadata,
+        ctx.system_program,
+        program_id,
+        GreetingMetadata::LEN,
+        &MetadataSeeds::new(ctx.greeting.key).with_bump(&[ctx.metadata_bump]),
+    )?;
+    exit!(cpi);
+    Ok(GreetingMetadata {
+        greeting: *ctx.greeting.key,
+        authority: *ctx.authority.key,
+        language: locale::DEFAULT_LANGUAGE,
+        uri: String::new(),
+    })
+}
+
+fn process_set_uri(program_id: &Pubkey, accounts: &[AccountInfo], uri: String) -> ProgramResult {
+    let span = enter!("set_uri");
+    let ctx = MetadataContext::load(program_id, accounts)?;
+    let mut metadata = load_or_create_metadata(program_id, &ctx, &span)?;
+    metadata.uri = uri;
+    metadata.serialize(&mut &mut ctx.metadata.data.borrow_mut()[..])?;
+
+    log_info!("Set metadata URI of {}", ctx.greeting.key);
+    Ok(())
+}
+
+fn process_set_language(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    language: [u8; 2],
+) -> ProgramResult {
+    let span = enter!("set_language");
+    let ctx = MetadataContext::load(program_id, accounts)?;
+    if !locale::is_supported(&language) {
+        return Err(HelloError::UnsupportedLanguage.into());
+    }
+    let mut metadata = load_or_create_metadata(program_id, &ctx, &span)?;
+    metadata.language = language;
+    metadata.serialize(&mut &mut ctx.metadata.data.borrow_mut()[..])?;
+
+    log_info!("Set language of {}", ctx.greeting.key);
+    Ok(())
+}
+
+// The window is created on first use like the other PDAs. The op_id is
+// checked and recorded before op runs, and a failing op fails the whole
+// instruction, so a rejected operation never uses up its op_id.
+fn process_idempotent(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    op_id: [u8; 16],
+    op: HelloInstruction,
+) -> ProgramResult {
+    let span = enter!("idempotent");
+    let ctx = IdempotentContext::load(program_id, accounts)?;
+
+    let mut recent_ops = if ctx.recent_ops.owner == program_id {
+        RecentOps::try_from_slice(&ctx.recent_ops.data.borrow())?
+    } else {
+        let cpi = enter!(span, "create recent ops account");
+        pda::create_pda_account(
+            ctx.payer,
+            ctx.recent_ops,
+            ctx.system_program,
+            program_id,
+            RecentOps::LEN,
+            &RecentOpsSeeds::new(ctx.greeting.key).with_bump(&[ctx.recent_ops_bump]),
+        )?;
+        exit!(cpi);
+        RecentOps::new(*ctx.greeting.key)
+    };
+    if recent_ops.contains(&op_id) {
+        msg!("Operation was already applied to {}", ctx.greeting.key);
+        return Err(HelloError::DuplicateOperation.into());
+    }
+    recent_ops.push(op_id);
+    recent_ops.serialize(&mut &mut ctx.recent_ops.data.borrow_mut()[..])?;
+
+    let counter = match op {
+        HelloInstruction::Increment => increment_counter(ctx.greeting, ctx.instructions)?,
+        HelloInstruction::Decrement => decrement_counter(ctx.greeting)?,
+        HelloInstruction::Set(value) => {
+            let mut data = ctx.greeting.data.borrow_mut();
+            check_direct_set(read_counter(&data)?)?;
+            write_counter(&mut data, value)?;
+            value
+        }
+        // unpack() never wraps anything else
+        _ => return Err(HelloError::InvalidInstruction.into()),
+    };
+    log_info!("Greeted {} time(s)!", counter);
+    Ok(())
+}
+
+// The first Deposit opens the vault and fixes its target, later ones only
+// add lamports. The lamports come from the authority with a system program
+// transfer, so the program never has to debit an account it doesn't own.
+fn process_deposit(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    amount: u64,
+    target: u32,
+) -> ProgramResult {
+    let span = enter!("deposit");
+    let ctx = DepositContext::load(program_id, accounts)?;
+
+    if ctx.vault.owner == program_id {
+        let vault = Vault::try_from_slice(&ctx.vault.data.borrow())?;
+        if vault.target != target {
+            msg!("Vault already unlocks at {}", vault.target);
+            return Err(ProgramError::InvalidArgument);
+        }
+    } else {
+        let cpi = enter!(span, "create vault account");
+        pda::create_pda_account(
+            ctx.authority,
+            ctx.vault,
+            ctx.system_program,
+            program_id,
+            Vault::LEN,
+            &VaultSeeds::new(ctx.greeting.key, ctx.authority.key).with_bump(&[ctx.vault_bump]),
+        )?;
+        exit!(cpi);
+        Vault {
+            greeting: *ctx.greeting.key,
+            authority: *ctx.authority.key,
+            target,
+        }
+        .serialize(&mut &mut ctx.vault.data.borrow_mut()[..])?;
+    }
+
+    let cpi = enter!(span, "transfer deposit");
+    invoke(
+        &system_instruction::transfer(ctx.authority.key, ctx.vault.key, amount),
+        &[
+            ctx.authority.clone(),
+            ctx.vault.clone(),
+            ctx.system_program.clone(),
+        ],
+    )?;
+    exit!(cpi);
+
+    log_info!("Deposited {} lamport(s), unlocked at {}", amount, target);
+    Ok(())
+}
+
+// Withdraw takes everything, rent included, and zeroes the data, so the
+// runtime drops the emptied vault at the end of the transaction. The vault
+// is owned by the program, which moves its lamports directly. Only the
+// counter is checked, however it got there, see state::Vault.
+fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let _span = enter!("withdraw");
+    let ctx = WithdrawContext::load(program_id, accounts)?;
+    let vault = Vault::try_from_slice(&ctx.vault.data.borrow())?;
+    let counter = read_counter(&ctx.greeting.data.borrow())?;
+    if counter < vault.target {
+        msg!("Vault unlocks at {}, counter is {}", vault.target, counter);
+        return Err(HelloError::VaultLocked.into());
+    }
+
+    let lamports = ctx.vault.lamports();
+    let balance = ctx
+        .authority
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    **ctx.authority.lamports.borrow_mut() = balance;
+    **ctx.vault.lamports.borrow_mut() = 0;
+    ctx.vault.data.borrow_mut().fill(0);
+
+    log_info!("Withdrew {} lamport(s)", lamports);
+    Ok(())
+}
+
+// A badge is created once and never changed, so an existing one is an
+// error rather than an overwrite. The counter only has to be at the
+// milestone when the badge is claimed, it may have moved on since.
+fn process_claim_badge(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    milestone: u32,
+) -> ProgramResult {
+    let span = enter!("claim_badge");
+    let ctx = BadgeContext::load(program_id, accounts, milestone)?;
+    if ctx.badge.owner == program_id {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    let counter = read_counter(&ctx.greeting.data.borrow())?;
+    if counter < milestone {
+        msg!("Counter is {}, the badge needs {}", counter, milestone);
+        return Err(HelloError::MilestoneNotReached.into());
+    }
+    let slot = Clock::get()?.slot;
+
+    let seeds = BadgeSeeds::new(ctx.greeting.key, milestone);
+    let cpi = enter!(span, "create badge account");
+    pda::create_pda_account(
+        ctx.payer,
+        ctx.badge,
+        ctx.system_program,
+        program_id,
+        Badge::LEN,
+        &seeds.with_bump(&[ctx.badge_bump]),
+    )?;
+    exit!(cpi);
+    Badge {
+        greeting: *ctx.greeting.key,
+        milestone,
+        slot,
+    }
+    .serialize(&mut &mut ctx.badge.data.borrow_mut()[..])?;
+
+    log_info!("{} reached {} greetings", ctx.greeting.key, milestone);
+    Ok(())
+}
+
+// The streak is created on the first check-in like the other PDAs. Epochs
+// come from the Clock sysvar, so a streak can't be extended faster than
+// the cluster moves through epochs.
+fn process_check_in(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let span = enter!("check_in");
+    let ctx = CheckInContext::load(program_id, accounts)?;
+    let epoch = Clock::get()?.epoch;
+
+    let mut streak = if ctx.streak.owner == program_id {
+        Streak::try_from_slice(&ctx.streak.data.borrow())?
+    } else {
+        let cpi = enter!(span, "create streak account");
+        pda::create_pda_account(
+            ctx.user,
+            ctx.streak,
+            ctx.system_program,
+            program_id,
+            Streak::LEN,
+            &StreakSeeds::new(ctx.greeting.key, ctx.user.key).with_bump(&[ctx.streak_bump]),
+        )?;
+        exit!(cpi);
+        Streak::new(*ctx.greeting.key, *ctx.user.key)
+    };
+    streak.check_in(epoch);
+    streak.serialize(&mut &mut ctx.streak.data.borrow_mut()[..])?;
+
+    let counter = increment_counter(ctx.greeting, ctx.instructions)?;
+    log_info!(
+        "Greeted {} time(s)! Streak {} (best {})",
+        counter,
+        streak.current_streak,
+        streak.best_streak
+    );
+    Ok(())
+}
+
+// Only reads, so it can be simulated for a self-audit, or put first in a
+// transaction with strict set so the rest only runs against the expected
+// deployment
+fn process_check_upgrade_authority(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    strict: bool,
+) -> ProgramResult {
+    let _span = enter!("check_upgrade_authority");
+    let ctx = ProgramDataContext::load(program_id, accounts)?;
+    let authority = upgrade::read_upgrade_authority(&ctx.program_data.data.borrow())?;
+    match authority {
+        Some(authority) => log_info!("Upgrade authority {}", authority),
+        None => log_info!("Program is immutable"),
+    }
+
+    if strict && authority != Some(config::upgrade_authority::id()) {
+        msg!("Not the expected upgrade authority");
+        return Err(HelloError::Unauthorized.into());
+    }
+    Ok(())
+}
+
+// Proposing again replaces the pending value and restarts the delay, but
+// only for the proposer who paid for the pending account, so ConfirmSet
+// always refunds them. The proposal is allowed whatever the counter, below
+// the threshold it is just a slower Set.
+fn process_propose_set(program_id: &Pubkey, accounts: &[AccountInfo], value: u32) -> ProgramResult {
+    let span = enter!("propose_set");
+    let ctx = ProposeSetContext::load(program_id, accounts)?;
+    read_counter(&ctx.greeting.data.borrow())?;
+    let slot = Clock::get()?.slot;
+
+    if ctx.pending.owner != program_id {
+        let cpi = enter!(span, "create pending account");
+        pda::create_pda_account(
+            ctx.proposer,
+            ctx.pending,
+            ctx.system_program,
+            program_id,
+            PendingSet::LEN,
+            &PendingSetSeeds::new(ctx.greeting.key).with_bump(&[ctx.pending_bump]),
+        )?;
+        exit!(cpi);
+    } else {
+        // a zeroed account, closed or made up front, records no greeting
+        let pending = PendingSet::try_from_slice(&ctx.pending.data.borrow())?;
+        if pending.greeting == *ctx.greeting.key && pending.proposer != *ctx.proposer.key {
+            msg!("A Set proposed by {} is pending", pending.proposer);
+            return Err(HelloError::Unauthorized.into());
+        }
+    }
+    PendingSet {
+        greeting: *ctx.greeting.key,
+        proposer: *ctx.proposer.key,
+        value,
+        slot,
+    }
+    .serialize(&mut &mut ctx.pending.data.borrow_mut()[..])?;
+
+    log_info!(
+        "Proposed setting {} to {} at slot {}",
+        ctx.greeting.key,
+        value,
+        slot
+    );
+    Ok(())
+}
+
+// The delay is counted in slots from the ProposeSet, so the two can't land
+// in the same transaction. The pending account is closed the same way as a
+// vault on Withdraw.
+fn process_confirm_set(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let _span = enter!("confirm_set");
+    let ctx = ConfirmSetContext::load(program_id, accounts)?;
+    let pending = PendingSet::try_from_slice(&ctx.pending.data.borrow())?;
+    // a zeroed record, closed or made up front, has no greeting
+    if pending.greeting != *ctx.greeting.key {
+        msg!("No Set of {} is pending", ctx.greeting.key);
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if pending.proposer != *ctx.proposer.key {
+        msg!("The rent goes back to the proposer {}", pending.proposer);
+        return Err(ProgramError::InvalidArgument);
+    }
+    let slot = Clock::get()?.slot;
+    if slot < pending.slot.saturating_add(config::SET_CONFIRM_DELAY) {
+        msg!(
+            "Proposed at slot {}, wait {} slots",
+            pending.slot,
+            config::SET_CONFIRM_DELAY
+        );
+        return Err(HelloError::ConfirmTooEarly.into());
+    }
+
+    let mut data = ctx.greeting.data.borrow_mut();
+    read_counter(&data)?;
+    write_counter(&mut data, pending.value)?;
+    close_pending_set(ctx.pending, ctx.proposer)?;
+
+    log_info!("Greeted {} time(s)!", pending.value);
+    Ok(())
+}
+
+// Only the proposer may cancel, whether or not the delay has passed, and
+// the counter is left as it is
+fn process_cancel_set(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let _span = enter!("cancel_set");
+    let ctx = CancelSetContext::load(program_id, accounts)?;
+    let pending = PendingSet::try_from_slice(&ctx.pending.data.borrow())?;
+    if pending.greeting != *ctx.greeting.key {
+        msg!("No Set of {} is pending", ctx.greeting.key);
+        return Err(ProgramError::UninitializedAccount);
+    }
+    if pending.proposer != *ctx.proposer.key {
+        msg!("Only the proposer {} can cancel the Set", pending.proposer);
+        return Err(HelloError::Unauthorized.into());
+    }
+    close_pending_set(ctx.pending, ctx.proposer)?;
+
+    log_info!("Cancelled the Set to {}", pending.value);
+    Ok(())
+}
+
+// Move the rent of the pending account to its proposer and zero it, so a
+// later ProposeSet starts from an empty record
+fn close_pending_set(pending: &AccountInfo, proposer: &AccountInfo) -> ProgramResult {
+    let balance = proposer
+        .lamports()
+        .checked_add(pending.lamports())
+        .ok_or(ProgramError::InvalidAccountData)?;
+    **proposer.lamports.borrow_mut() = balance;
+    **pending.lamports.borrow_mut() = 0;
+    pending.data.borrow_mut().fill(0);
+    Ok(())
+}
+
+// Like the metadata record, the first SetAllowlist makes its signer the
+// authority and later ones have to be signed by them
+fn process_set_allowlist(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    root: [u8; 32],
+) -> ProgramResult {
+    let span = enter!("set_allowlist");
+    let ctx = AllowlistContext::load(program_id, accounts)?;
+    if ctx.allowlist.owner == program_id {
+        let allowlist = Allowlist::try_from_slice(&ctx.allowlist.data.borrow())?;
+        if allowlist.authority != *ctx.authority.key {
+            msg!("Only the allowlist authority can change the root");
+            return Err(HelloError::Unauthorized).with_context(
+                "allowlist authority",
+                1,
+                allowlist.authority,
+                ctx.authority.key,
+            );
+        }
+    } else {
+        let cpi = enter!(span, "create allowlist account");
+        pda::create_pda_account(
+            ctx.authority,
+            ctx.allowlist,
+            ctx.system_program,
+            program_id,
+            Allowlist::LEN,
+            &AllowlistSeeds::new(ctx.greeting.key).with_bump(&[ctx.allowlist_bump]),
+        )?;
+        exit!(cpi);
+    }
+    Allowlist {
+        greeting: *ctx.greeting.key,
+        authority: *ctx.authority.key,
+        root,
+    }
+    .serialize(&mut &mut ctx.allowlist.data.borrow_mut()[..])?;
+
+    log_info!("Set allowlist of {}", ctx.greeting.key);
+    Ok(())
+}
+
+// The proof costs one hash per level, MAX_PROOF_LEN at most, so the check
+// stays cheap however large the allowlist is
+fn process_allowlisted_increment(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    proof: Vec<[u8; 32]>,
+) -> ProgramResult {
+    let _span = enter!("allowlisted_increment");
+    let ctx = AllowlistedIncrementContext::load(program_id, accounts)?;
+    let allowlist = Allowlist::try_from_slice(&ctx.allowlist.data.borrow())?;
+    if !merkle::verify(&allowlist.root, merkle::leaf(ctx.member.key), &proof) {
+        msg!("{} is not on the allowlist", ctx.member.key);
+        return Err(HelloError::NotAllowlisted.into());
+    }
+    let counter = increment_counter(ctx.greeting, ctx.instructions)?;
+
+    log_info!("Greeted {} time(s)!", counter);
+    Ok(())
+}
+
+// Only greetings with a signer can be told apart, so the statistics are
+// kept by this instruction alone and not by Increment. GreeterStats is the
+// largest account the program has, it is kept boxed to leave the stack
+// frame for the CPI.
+fn process_signed_increment(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+    let span = enter!("signed_increment");
+    let ctx = SignedIncrementContext::load(program_id, accounts)?;
+    let counter = increment_counter(ctx.greeting, ctx.instructions)?;
+
+    let mut stats = if ctx.stats.owner == program_id {
+        GreeterStats::unpack_boxed(&ctx.stats.data.borrow())?
+    } else {
+        let cpi = enter!(span, "create stats account");
+        pda::create_pda_account(
+            ctx.greeter,
+            ctx.stats,
+            ctx.system_program,
+            program_id,
+            GreeterStats::LEN,
+            &GreeterStatsSeeds::new(ctx.greeting.key).with_bump(&[ctx.stats_bump]),
+        )?;
+        exit!(cpi);
+        GreeterStats::new_boxed(*ctx.greeting.key)
+    };
+    stats.record(ctx.greeter.key);
+    stats.pack(&mut ctx.stats.data.borrow_mut())?;
+
+    log_info!(
+        "Greeted {} time(s) by about {} greeter(s)!",
+        counter,
+        stats.unique_greeters_hll()
+    );
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//src/program-rust/src/security.rs
+// security.txt metadata for the deployed program.
+//
+// The values are written once below and expanded into:
+//   - public constants, so clients can print the same contact details
+//   - a `security_txt` section in the program binary (with the
+//     `security-txt` feature), which explorers read to show who to contact
+//     about vulnerabilities
+
+macro_rules! security_metadata {
+    (
+        name: $name:literal,
+        project_url: $project_url:literal,
+        contacts: $contacts:literal,
+        policy: $policy:literal,
+        source_code: $source_code:literal
+    ) => {
+        /// Program name shown by explorers
+        pub const NAME: &str = $name;
+        /// Home page of the project
+        pub const PROJECT_URL: &str = $project_url;
+        /// Comma separated list of ways to reach the maintainers
+        pub const CONTACTS: &str = $contacts;
+        /// Where the security policy lives
+        pub const POLICY: &str = $policy;
+        /// Repository the deployed binary was built from
+        pub const SOURCE_CODE: &str = $source_code;
+
+        // only the program itself embeds the section, crates depending on us
+        // with `no-entrypoint` would otherwise export a second copy
+        #[cfg(all(feature = "security-txt", not(feature = "no-entrypoint")))]
+        solana_security_txt::security_txt! {
+            name: $name,
+            project_url: $project_url,
+            contacts: $contacts,
+            policy: $policy,
+            source_code: $source_code
+        }
+    };
+}
+
+security_metadata! {
+    name: "Solana Hello World",
+    project_url: "https://github.com/grajnikanth/solana_hello_world",
+    contacts: "link:https://github.com/grajnikanth/solana_hello_world/issues",
+    policy: "https://github.com/grajnikanth/solana_hello_world/blob/master/README.md",
+    source_code: "https://github.com/grajnikanth/solana_hello_world"
+}
@@ -0,0 +1,276 @@
+// Fixtures for the unit tests. AccountInfo only borrows its lamports and
+// data, so a TestAccount owns them and hands out AccountInfos pointing into
+// itself. Every builder method has the same meaning as the AccountInfo::new
+// argument of the same name, fields without one (lamports) are set directly.
+
+use crate::config;
+use crate::state::GreetingAccount;
+use solana_program::{
+    account_info::AccountInfo, clock::Epoch, instruction::Instruction, message::Message,
+    program_stubs, pubkey::Pubkey, system_program, sysvar,
+};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::{Cell, RefCell};
+use std::sync::Once;
+
+pub struct TestAccount {
+    pub key: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+    pub executable: bool,
+}
+
+impl TestAccount {
+    /// A readonly, unsigned account owned by `owner` with a unique key, no
+    /// lamports and no data
+    pub fn new(owner: &Pubkey) -> Self {
+        Self {
+            key: Pubkey::new_unique(),
+            owner: *owner,
+            lamports: 0,
+            data: vec![],
+            is_signer: false,
+            is_writable: false,
+            executable: false,
+        }
+    }
+
+    /// A writable greeting account of GreetingAccount::LEN zeroed bytes
+    pub fn greeting(program_id: &Pubkey) -> Self {
+        Self::new(program_id)
+            .data_len(GreetingAccount::LEN)
+            .writable()
+    }
+
+    /// A writable, signing wallet owned by the system program
+    pub fn wallet() -> Self {
+        Self::new(&system_program::id()).signer().writable()
+    }
+
+    /// The system program itself
+    pub fn system_program() -> Self {
+        Self::new(&system_program::id())
+            .key(system_program::id())
+            .executable()
+    }
+
+    /// The instructions sysvar of a transaction made of `instructions`,
+    /// the first of them running
+    pub fn instructions_sysvar(instructions: &[Instruction]) -> Self {
+        let message = Message::new(instructions, Some(&Pubkey::new_unique()));
+        let mut data = message.serialize_instructions(true);
+        // the index of the current instruction goes at the end
+        data.extend_from_slice(&0u16.to_le_bytes());
+        Self::new(&sysvar::id())
+            .key(sysvar::instructions::id())
+            .data(data)
+    }
+
+    /// The instructions sysvar of a transaction with an SPL Memo, see
+    /// with_memo()
+    pub fn memo_sysvar() -> Self {
+        let memo = Instruction::new_with_bytes(config::memo::id(), b"test", vec![]);
+        Self::instructions_sysvar(&[memo])
+    }
+
+    pub fn key(mut self, key: Pubkey) -> Self {
+        self.key = key;
+        self
+    }
+
+    pub fn data(mut self, data: Vec<u8>) -> Self {
+        self.data = data;
+        self
+    }
+
+    pub fn data_len(self, len: usize) -> Self {
+        self.data(vec![0; len])
+    }
+
+    pub fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+
+    pub fn writable(mut self) -> Self {
+        self.is_writable = true;
+        self
+    }
+
+    pub fn executable(mut self) -> Self {
+        self.executable = true;
+        self
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            self.is_writable,
+            &mut self.lamports,
+            &mut self.data,
+            &self.owner,
+            self.executable,
+            Epoch::default(),
+        )
+    }
+}
+
+/// `accounts` of an instruction that increments a counter, followed in a
+/// `require-memo` build by `sysvar`, a TestAccount::memo_sysvar()
+pub fn with_memo<'a>(
+    mut accounts: Vec<AccountInfo<'a>>,
+    sysvar: &AccountInfo<'a>,
+) -> Vec<AccountInfo<'a>> {
+    if cfg!(feature = "require-memo") {
+        accounts.push(sysvar.clone());
+    }
+    accounts
+}
+
+// The system allocator, counting the allocations made on a thread while it
+// is inside count_allocations()
+struct CountingAllocator;
+
+thread_local! {
+    #[allow(clippy::declare_interior_mutable_const)]
+    static ALLOCATIONS: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // try_with, the thread local is gone while a thread shuts down
+        let _ = ALLOCATIONS.try_with(|count| {
+            if let Some(n) = count.get() {
+                count.set(Some(n + 1));
+            }
+        });
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// The default stubs used off chain drop return data, and print log lines,
+// which allocates where the syscalls don't. These keep the return data of
+// each thread and count the lines of a thread while it is inside
+// count_allocations(). They are installed once and never swapped out, so
+// tests running at the same time can't take each other's stubs away.
+struct TestStubs;
+
+static INSTALL: Once = Once::new();
+
+thread_local! {
+    #[allow(clippy::declare_interior_mutable_const)]
+    static RETURN_DATA: RefCell<Vec<u8>> = const { RefCell::new(vec![]) };
+    #[allow(clippy::declare_interior_mutable_const)]
+    static LOGGED: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+// Whether the line was counted rather than to be printed
+fn count_logged() -> bool {
+    LOGGED.with(|count| match count.get() {
+        Some(n) => {
+            count.set(Some(n + 1));
+            true
+        }
+        None => false,
+    })
+}
+
+impl program_stubs::SyscallStubs for TestStubs {
+    fn sol_log(&self, message: &str) {
+        if !count_logged() {
+            println!("{}", message);
+        }
+    }
+
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        if !count_logged() {
+            println!("data: {:?}", fields);
+        }
+    }
+
+    fn sol_set_return_data(&mut self, data: &[u8]) {
+        RETURN_DATA.with(|return_data| *return_data.borrow_mut() = data.to_vec());
+    }
+}
+
+fn install_stubs() {
+    INSTALL.call_once(|| {
+        program_stubs::set_syscall_stubs(Box::new(TestStubs));
+    });
+}
+
+/// Run `f` and count the heap allocations it makes. Its log lines are
+/// counted instead of printed meanwhile, and the count of those is
+/// returned too.
+pub fn count_allocations<R>(f: impl FnOnce() -> R) -> (R, usize, usize) {
+    install_stubs();
+    LOGGED.with(|count| count.set(Some(0)));
+    ALLOCATIONS.with(|count| count.set(Some(0)));
+    let result = f();
+    let allocations = ALLOCATIONS.with(|count| count.replace(None)).unwrap_or(0);
+    let logged = LOGGED.with(|count| count.replace(None)).unwrap_or(0);
+    (result, allocations, logged)
+}
+
+/// The input the loader passes the entrypoint for `accounts`, none of them
+/// duplicates, laid out the way entrypoint::deserialize() reads it. It is
+/// made of u64s to be aligned like the loader's.
+#[cfg(not(feature = "no-entrypoint"))]
+pub fn serialize_input(program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> Vec<u64> {
+    use solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+
+    fn pad(input: &mut Vec<u8>) {
+        input.resize(input.len() + (8 - input.len() % 8) % 8, 0);
+    }
+
+    let mut input = vec![];
+    input.extend_from_slice(&(accounts.len() as u64).to_le_bytes());
+    for account in accounts {
+        let flags = [account.is_signer, account.is_writable, account.executable];
+        // not a duplicate, the flags and 4 bytes of padding
+        input.push(u8::MAX);
+        input.extend(flags.iter().map(|&flag| flag as u8));
+        input.extend_from_slice(&[0; 4]);
+        input.extend_from_slice(account.key.as_ref());
+        input.extend_from_slice(account.owner.as_ref());
+        input.extend_from_slice(&account.lamports().to_le_bytes());
+        input.extend_from_slice(&(account.data_len() as u64).to_le_bytes());
+        input.extend_from_slice(&account.data.borrow());
+        // the room the account could grow into
+        input.resize(input.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+        pad(&mut input);
+        input.extend_from_slice(&account.rent_epoch.to_le_bytes());
+    }
+    input.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    input.extend_from_slice(data);
+    input.extend_from_slice(program_id.as_ref());
+    pad(&mut input);
+    input
+        .chunks(8)
+        .map(|chunk| {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            u64::from_le_bytes(word)
+        })
+        .collect()
+}
+
+/// Run `f` and return the return data it set on this thread, empty if it
+/// set none
+pub fn return_data<R>(f: impl FnOnce() -> R) -> (R, Vec<u8>) {
+    install_stubs();
+    RETURN_DATA.with(|return_data| return_data.borrow_mut().clear());
+    let result = f();
+    (result, RETURN_DATA.with(|return_data| return_data.take()))
+}
@@ -0,0 +1,82 @@
+//src/program-rust/src/upgrade.rs
+// The program's own deployment, read from its ProgramData account under
+// the upgradeable BPF loader
+//
+// The loader stores its state with bincode, which the program doesn't
+// otherwise depend on. Only the ProgramData header is needed, so it is read
+// at fixed offsets the same way read_counter() reads the counter:
+//
+//   [tag: u32 = 3][slot: u64][authority tag: u8][authority: 32 bytes]
+//
+// The authority bytes are only there when the tag is 1. A program made
+// immutable has tag 0 and nothing after it.
+
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use std::convert::TryInto;
+
+// bincode tag of UpgradeableLoaderState::ProgramData
+const PROGRAM_DATA_TAG: u32 = 3;
+const AUTHORITY_TAG_OFFSET: usize = 4 + 8;
+const AUTHORITY_OFFSET: usize = AUTHORITY_TAG_OFFSET + 1;
+
+/// The upgrade authority in the data of a ProgramData account, None for an
+/// immutable program. Check the account is the program's own first, see
+/// ProgramDataContext.
+pub fn read_upgrade_authority(data: &[u8]) -> Result<Option<Pubkey>, ProgramError> {
+    let tag = data
+        .get(..4)
+        .and_then(|tag| tag.try_into().ok())
+        .map(u32::from_le_bytes);
+    if tag != Some(PROGRAM_DATA_TAG) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    match data.get(AUTHORITY_TAG_OFFSET) {
+        Some(0) => Ok(None),
+        Some(1) => data
+            .get(AUTHORITY_OFFSET..AUTHORITY_OFFSET + 32)
+            .map(|key| Some(Pubkey::new(key)))
+            .ok_or(ProgramError::InvalidAccountData),
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program::bpf_loader_upgradeable::UpgradeableLoaderState;
+
+    // the offsets above have to agree with the real bincode encoding
+    #[test]
+    fn test_read_matches_loader_layout() {
+        let authority = Pubkey::new_unique();
+        for expected in [Some(authority), None].iter() {
+            let state = UpgradeableLoaderState::ProgramData {
+                slot: 42,
+                upgrade_authority_address: *expected,
+            };
+            let mut data = bincode::serialize(&state).unwrap();
+            assert_eq!(read_upgrade_authority(&data), Ok(*expected));
+
+            // the program bits that follow the header don't matter
+            data.extend_from_slice(&[0xff; 64]);
+            assert_eq!(read_upgrade_authority(&data), Ok(*expected));
+        }
+        assert_eq!(
+            UpgradeableLoaderState::programdata_data_offset(),
+            Ok(AUTHORITY_OFFSET + 32)
+        );
+
+        let program = bincode::serialize(&UpgradeableLoaderState::Program {
+            programdata_address: authority,
+        })
+        .unwrap();
+        assert_eq!(
+            read_upgrade_authority(&program),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(
+            read_upgrade_authority(&[3, 0, 0, 0]),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+}
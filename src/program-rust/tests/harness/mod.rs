@@ -0,0 +1,165 @@
+// Shared setup for the integration tests, so each test reads as the
+// scenario it checks instead of ProgramTest and BanksClient plumbing.
+//
+// Setup collects the accounts and programs the test needs before the bank
+// starts, Harness then sends transactions and reads accounts back. Every
+// transaction gets a fresh blockhash, so sending the same instruction twice
+// is two transactions and not a rejected duplicate. With `custom-heap` it
+// also starts with the heap request such a build needs, and with
+// `require-memo` it carries a memo and the instructions sysvar.
+
+use borsh::BorshDeserialize;
+use helloworld::{process_instruction, GreetingAccount};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+    transport::TransportError,
+};
+
+pub struct Setup {
+    pub program_id: Pubkey,
+    /// For anything the helpers below don't cover, e.g. add_program()
+    pub program_test: ProgramTest,
+}
+
+impl Setup {
+    /// The program deployed at a unique id
+    pub fn new() -> Self {
+        let program_id = Pubkey::new_unique();
+        let program_test = ProgramTest::new(
+            "helloworld", // Run the BPF version with `cargo test-bpf`
+            program_id,
+            processor!(process_instruction), // Run the native version with `cargo test`
+        );
+        Self {
+            program_id,
+            program_test,
+        }
+    }
+
+    /// Add a zeroed greeting account owned by the program
+    pub fn greeting(&mut self) -> Pubkey {
+        let pubkey = Pubkey::new_unique();
+        let program_id = self.program_id;
+        self.account(pubkey, vec![0; GreetingAccount::LEN], &program_id);
+        pubkey
+    }
+
+    /// Add an account holding `data`, owned by `owner`
+    pub fn account(&mut self, pubkey: Pubkey, data: Vec<u8>, owner: &Pubkey) {
+        self.program_test.add_account(
+            pubkey,
+            Account {
+                lamports: 1_000_000,
+                data,
+                owner: *owner,
+                ..Account::default()
+            },
+        );
+    }
+
+    pub async fn start(self) -> Harness {
+        Harness {
+            program_id: self.program_id,
+            context: self.program_test.start_with_context().await,
+        }
+    }
+}
+
+pub struct Harness {
+    pub program_id: Pubkey,
+    pub context: ProgramTestContext,
+}
+
+impl Harness {
+    /// The fee payer of every transaction
+    pub fn payer(&self) -> Pubkey {
+        self.context.payer.pubkey()
+    }
+
+    /// Create a greeting account with a system program transaction, as
+    /// clients do
+    pub async fn create_greeting(&mut self) -> Pubkey {
+        let greeting = Keypair::new();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let ix = system_instruction::create_account(
+            &self.payer(),
+            &greeting.pubkey(),
+            rent.minimum_balance(GreetingAccount::LEN),
+            GreetingAccount::LEN as u64,
+            &self.program_id,
+        );
+        self.send_ixs(&[ix], &[&greeting]).await.unwrap();
+        greeting.pubkey()
+    }
+
+    pub async fn send_ix(&mut self, ix: Instruction) -> Result<(), TransportError> {
+        self.send_ixs(&[ix], &[]).await
+    }
+
+    /// Send `ixs` in one transaction signed by the payer and `signers`
+    pub async fn send_ixs(
+        &mut self,
+        ixs: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransportError> {
+        #[cfg(feature = "require-memo")]
+        let ixs = &hello_client::with_memo(&self.program_id, ixs, "test");
+        #[cfg(feature = "custom-heap")]
+        let ixs = &hello_client::with_heap_frame(ixs);
+        self.send_raw_ixs(ixs, signers).await
+    }
+
+    /// send_ixs() without the instructions it adds for the build's features
+    pub async fn send_raw_ixs(
+        &mut self,
+        ixs: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Result<(), TransportError> {
+        let blockhash = self
+            .context
+            .banks_client
+            .get_new_blockhash(&self.context.last_blockhash)
+            .await?
+            .0;
+        self.context.last_blockhash = blockhash;
+
+        let mut transaction = Transaction::new_with_payer(ixs, Some(&self.payer()));
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        transaction.sign(&all_signers, blockhash);
+        self.context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+    }
+
+    /// The raw account, panicking if it doesn't exist
+    pub async fn account(&mut self, pubkey: Pubkey) -> Account {
+        self.context
+            .banks_client
+            .get_account(pubkey)
+            .await
+            .expect("get_account")
+            .unwrap_or_else(|| panic!("account {} not found", pubkey))
+    }
+
+    /// The account's data decoded as `T`
+    pub async fn read_state<T: BorshDeserialize>(&mut self, pubkey: Pubkey) -> T {
+        let account = self.account(pubkey).await;
+        T::deserialize(&mut &*account.data).unwrap()
+    }
+
+    pub async fn read_counter(&mut self, greeting: Pubkey) -> u32 {
+        self.read_state::<GreetingAccount>(greeting).await.counter
+    }
+
+    pub fn warp_to_slot(&mut self, slot: u64) {
+        self.context.warp_to_slot(slot).unwrap();
+    }
+}
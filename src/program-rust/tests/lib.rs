@@ -1,15 +1,40 @@
-use borsh::BorshDeserialize;
-use helloworld::{process_instruction, GreetingAccount};
+use borsh::{BorshDeserialize, BorshSerialize};
+use harness::Setup;
+#[cfg(feature = "require-memo")]
+use helloworld::error::HelloError;
+use helloworld::{
+    config,
+    instruction::{self, HelloInstruction},
+    merkle, pda, process_instruction,
+    seeds::{self, AllowlistSeeds, GreeterStatsSeeds, PendingSetSeeds, StreakSeeds, VaultSeeds},
+    state::{
+        Allowlist, Attestation, GreeterStats, PendingSet, Poll, Streak, Vault,
+        SET_CONFIRM_THRESHOLD,
+    },
+    GreetingAccount,
+};
+#[cfg(feature = "test-bpf")]
+use helloworld::{
+    seeds::BadgeSeeds,
+    state::{Badge, VoteReceipt},
+};
 use solana_program_test::*;
 use solana_sdk::{
     account::Account,
+    bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+    hash::hash,
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
-    signature::Signer,
+    signature::{keypair_from_seed, Keypair, Signer},
+    system_program,
     transaction::Transaction,
 };
+#[cfg(feature = "require-memo")]
+use solana_sdk::{instruction::InstructionError, transaction::TransactionError};
 use std::mem;
 
+mod harness;
+
 #[tokio::test]
 async fn test_helloworld() {
     let program_id = Pubkey::new_unique();
@@ -45,14 +70,15 @@ async fn test_helloworld() {
     );
 
     // Greet once
-    let mut transaction = Transaction::new_with_payer(
-        &[Instruction::new_with_bincode(
-            program_id,
-            &[0], // ignored but makes the instruction unique in the slot
-            vec![AccountMeta::new(greeted_pubkey, false)],
-        )],
-        Some(&payer.pubkey()),
-    );
+    let ixs = [Instruction::new_with_bytes(
+        program_id,
+        &[0], // HelloInstruction::Increment
+        vec![AccountMeta::new(greeted_pubkey, false)],
+    )];
+    // which a `require-memo` build only runs next to a memo
+    #[cfg(feature = "require-memo")]
+    let ixs = hello_client::with_memo(&program_id, &ixs, "test");
+    let mut transaction = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
     transaction.sign(&[&payer], recent_blockhash);
     banks_client.process_transaction(transaction).await.unwrap();
 
@@ -69,15 +95,19 @@ async fn test_helloworld() {
         1
     );
 
-    // Greet again
-    let mut transaction = Transaction::new_with_payer(
-        &[Instruction::new_with_bincode(
-            program_id,
-            &[1], // ignored but makes the instruction unique in the slot
-            vec![AccountMeta::new(greeted_pubkey, false)],
-        )],
-        Some(&payer.pubkey()),
-    );
+    // Greet again, with a fresh blockhash so the transaction is not a duplicate
+    let (recent_blockhash, _) = banks_client
+        .get_new_blockhash(&recent_blockhash)
+        .await
+        .unwrap();
+    let ixs = [Instruction::new_with_bytes(
+        program_id,
+        &[0], // HelloInstruction::Increment
+        vec![AccountMeta::new(greeted_pubkey, false)],
+    )];
+    #[cfg(feature = "require-memo")]
+    let ixs = hello_client::with_memo(&program_id, &ixs, "test");
+    let mut transaction = Transaction::new_with_payer(&ixs, Some(&payer.pubkey()));
     transaction.sign(&[&payer], recent_blockhash);
     banks_client.process_transaction(transaction).await.unwrap();
 
@@ -94,3 +124,752 @@ async fn test_helloworld() {
         2
     );
 }
+
+#[tokio::test]
+async fn test_batch_self_cpi() {
+    let mut setup = Setup::new();
+    let greeting = setup.greeting();
+    let mut harness = setup.start().await;
+    let program_id = harness.program_id;
+
+    harness
+        .send_ix(instruction::batch(
+            &program_id,
+            &greeting,
+            vec![
+                HelloInstruction::Increment,
+                HelloInstruction::Set(10),
+                HelloInstruction::Increment,
+                HelloInstruction::Decrement,
+                HelloInstruction::Decrement,
+            ],
+        ))
+        .await
+        .unwrap();
+    assert_eq!(harness.read_counter(greeting).await, 9);
+
+    // a failing op fails the whole batch, nothing before it is kept
+    assert!(harness
+        .send_ix(instruction::batch(
+            &program_id,
+            &greeting,
+            vec![HelloInstruction::Set(0), HelloInstruction::Decrement],
+        ))
+        .await
+        .is_err());
+    assert_eq!(harness.read_counter(greeting).await, 9);
+}
+
+#[tokio::test]
+async fn test_mirror_increment() {
+    let mirror_program_id = config::mirror::id();
+    let mirror_pubkey = Pubkey::new_unique();
+
+    let mut setup = Setup::new();
+    // the same program deployed a second time at the configured mirror id
+    setup.program_test.add_program(
+        "helloworld",
+        mirror_program_id,
+        processor!(process_instruction),
+    );
+    let greeting = setup.greeting();
+    setup.account(
+        mirror_pubkey,
+        vec![0_u8; GreetingAccount::LEN],
+        &mirror_program_id,
+    );
+    let mut harness = setup.start().await;
+    let program_id = harness.program_id;
+
+    harness
+        .send_ix(instruction::mirror_increment(
+            &program_id,
+            &greeting,
+            &mirror_pubkey,
+        ))
+        .await
+        .unwrap();
+
+    for pubkey in [greeting, mirror_pubkey] {
+        assert_eq!(harness.read_counter(pubkey).await, 1);
+    }
+}
+
+#[tokio::test]
+async fn test_create_greeting_and_increment() {
+    let mut harness = Setup::new().start().await;
+    let program_id = harness.program_id;
+    let greeting = harness.create_greeting().await;
+    assert_eq!(harness.read_counter(greeting).await, 0);
+
+    // the same instruction twice is two transactions, not a duplicate
+    for _ in 0..2 {
+        harness
+            .send_ix(instruction::increment(&program_id, &greeting))
+            .await
+            .unwrap();
+    }
+    assert_eq!(harness.read_counter(greeting).await, 2);
+}
+
+#[tokio::test]
+async fn test_same_transaction_interleaving() {
+    let mut setup = Setup::new();
+    let greeting = setup.greeting();
+    let mut harness = setup.start().await;
+    let program_id = harness.program_id;
+
+    // instructions run in order, each one seeing the writes of the last, and
+    // nothing limits how often one account is touched within a transaction
+    harness
+        .send_ixs(
+            &[
+                instruction::increment(&program_id, &greeting),
+                instruction::increment(&program_id, &greeting),
+                instruction::decrement(&program_id, &greeting),
+                instruction::set(&program_id, &greeting, 10),
+                instruction::increment(&program_id, &greeting),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(harness.read_counter(greeting).await, 11);
+
+    // a batch is one instruction among the others, not a separate scope
+    harness
+        .send_ixs(
+            &[
+                instruction::decrement(&program_id, &greeting),
+                instruction::batch(
+                    &program_id,
+                    &greeting,
+                    vec![HelloInstruction::Set(5), HelloInstruction::Increment],
+                ),
+                instruction::increment(&program_id, &greeting),
+            ],
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(harness.read_counter(greeting).await, 7);
+
+    // a failure in a later instruction rolls back the earlier ones too
+    assert!(harness
+        .send_ixs(
+            &[
+                instruction::set(&program_id, &greeting, 0),
+                instruction::batch(&program_id, &greeting, vec![HelloInstruction::Increment]),
+                instruction::decrement(&program_id, &greeting),
+                instruction::decrement(&program_id, &greeting),
+            ],
+            &[],
+        )
+        .await
+        .is_err());
+    assert_eq!(harness.read_counter(greeting).await, 7);
+}
+
+// Golden values: sha256 of the account bytes left by the scripted sequence
+// in test_golden_state_hashes. A change to either means the layout or the
+// semantics of an instruction changed, update them only if that's intended.
+const GOLDEN_GREETING_HASH: &str = "5dBJ1UBwqcC9N5huckaSsPgFg1UyxEbvbPBhLviCjd9u";
+const GOLDEN_POLL_HASH: &str = "Hc2pepcfCqo5QEqQVWbKofR1DJSomQcXBtAVzW77LD9U";
+
+#[tokio::test]
+async fn test_golden_state_hashes() {
+    let source_pubkey = Pubkey::new_unique();
+    let poll_pubkey = Pubkey::new_unique();
+    // the poll stores its authority, so its key has to be the same every run
+    let authority = keypair_from_seed(&[7; 32]).unwrap();
+
+    let mut setup = Setup::new();
+    let program_id = setup.program_id;
+    let greeting = setup.greeting();
+    setup.account(
+        source_pubkey,
+        20_u32.to_le_bytes().to_vec(),
+        &config::foreign::id(),
+    );
+    setup.account(poll_pubkey, vec![0_u8; Poll::LEN], &program_id);
+    let mut harness = setup.start().await;
+
+    let script = vec![
+        instruction::increment(&program_id, &greeting),
+        instruction::increment(&program_id, &greeting),
+        instruction::decrement(&program_id, &greeting),
+        instruction::set(&program_id, &greeting, 41),
+        instruction::copy_from(&program_id, &greeting, &source_pubkey),
+        instruction::batch(
+            &program_id,
+            &greeting,
+            vec![
+                HelloInstruction::Increment,
+                HelloInstruction::Increment,
+                HelloInstruction::Decrement,
+            ],
+        ),
+        instruction::create_poll(
+            &program_id,
+            &poll_pubkey,
+            &authority.pubkey(),
+            vec!["yes".to_string(), "no".to_string(), "maybe".to_string()],
+        ),
+        instruction::close_poll(&program_id, &poll_pubkey, &authority.pubkey()),
+    ];
+    // one instruction per transaction, in order, like separate client calls
+    for ix in script {
+        let poll_ix = ix
+            .accounts
+            .iter()
+            .any(|meta| meta.pubkey == authority.pubkey());
+        if poll_ix {
+            harness.send_ixs(&[ix], &[&authority]).await.unwrap();
+        } else {
+            harness.send_ix(ix).await.unwrap();
+        }
+    }
+
+    assert_eq!(harness.read_counter(greeting).await, 21);
+    let greeting_data = harness.account(greeting).await.data;
+    assert_eq!(hash(&greeting_data).to_string(), GOLDEN_GREETING_HASH);
+    let poll_data = harness.account(poll_pubkey).await.data;
+    assert_eq!(hash(&poll_data).to_string(), GOLDEN_POLL_HASH);
+}
+
+// Creating the receipt resizes an account through a CPI, which the native
+// processor can't do, so this only runs under `cargo test-bpf`
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_votes_are_deduplicated() {
+    let mut setup = Setup::new();
+    let greeting = setup.greeting();
+    let mut harness = setup.start().await;
+    let program_id = harness.program_id;
+    let payer = harness.payer();
+
+    // the payer also votes, so it upvotes once and the second vote fails
+    harness
+        .send_ix(instruction::upvote(&program_id, &greeting, &payer))
+        .await
+        .unwrap();
+    assert!(harness
+        .send_ix(instruction::downvote(&program_id, &greeting, &payer))
+        .await
+        .is_err());
+
+    let (receipt, _) = pda::find_receipt_address(&program_id, &greeting, &payer);
+    assert_eq!(harness.account(receipt).await.owner, program_id);
+    assert_eq!(
+        harness.read_state::<VoteReceipt>(receipt).await,
+        VoteReceipt {
+            target: greeting,
+            voter: payer,
+            choice: VoteReceipt::UP,
+        }
+    );
+    assert_eq!(harness.read_counter(greeting).await, 1);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_badge_claimed_once() {
+    let mut setup = Setup::new();
+    let greeting = setup.greeting();
+    let mut harness = setup.start().await;
+    let program_id = harness.program_id;
+    let payer = harness.payer();
+
+    assert!(harness
+        .send_ix(instruction::claim_badge(&program_id, &greeting, &payer, 10))
+        .await
+        .is_err());
+    harness
+        .send_ix(instruction::set(&program_id, &greeting, 150))
+        .await
+        .unwrap();
+    for milestone in [10, 100].iter() {
+        let claim = instruction::claim_badge(&program_id, &greeting, &payer, *milestone);
+        harness.send_ix(claim).await.unwrap();
+        let (badge, _) = BadgeSeeds::new(&greeting, *milestone).pda(&program_id);
+        let record = harness.read_state::<Badge>(badge).await;
+        assert_eq!(record.greeting, greeting);
+        assert_eq!(record.milestone, *milestone);
+    }
+    assert!(harness
+        .send_ix(instruction::claim_badge(&program_id, &greeting, &payer, 10))
+        .await
+        .is_err());
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_attest_overwrites_record() {
+    let mut setup = Setup::new();
+    let greeting = setup.greeting();
+    let mut harness = setup.start().await;
+    let program_id = harness.program_id;
+    let payer = harness.payer();
+    let (attestation, _) = pda::find_attestation_address(&program_id, &greeting);
+
+    for hash in [[1_u8; 32], [2_u8; 32]].iter() {
+        harness
+            .send_ix(instruction::attest(&program_id, &greeting, &payer, *hash))
+            .await
+            .unwrap();
+
+        let record = harness.read_state::<Attestation>(attestation).await;
+        assert_eq!(record.greeting, greeting);
+        assert_eq!(record.hash, *hash);
+        assert_eq!(record.attester, payer);
+    }
+}
+
+// Attest stores the slot it ran in. The attestation account already exists,
+// so no CPI is needed and this runs on the native processor too.
+#[tokio::test]
+async fn test_attest_records_warped_slot() {
+    let mut setup = Setup::new();
+    let program_id = setup.program_id;
+    let greeting = setup.greeting();
+    let (attestation, _) = pda::find_attestation_address(&program_id, &greeting);
+    setup.account(attestation, vec![0_u8; Attestation::LEN], &program_id);
+    let mut harness = setup.start().await;
+    let payer = harness.payer();
+
+    for (warp_slot, hash) in [(100, [1_u8; 32]), (5_000, [2_u8; 32])].iter() {
+        harness.warp_to_slot(*warp_slot);
+        harness
+            .send_ix(instruction::attest(&program_id, &greeting, &payer, *hash))
+            .await
+            .unwrap();
+
+        let record = harness.read_state::<Attestation>(attestation).await;
+        assert_eq!(record.slot, *warp_slot);
+        assert_eq!(record.hash, *hash);
+    }
+}
+
+// The vault is opened up front since creating it is a CPI the native
+// processor can't do. Deposit's transfer into it is a plain CPI and runs
+// natively. The unlock only looks at the counter, so a Set to the target
+// in the same transaction opens it too.
+#[tokio::test]
+async fn test_vault_unlocks_at_target() {
+    let mut setup = Setup::new();
+    let program_id = setup.program_id;
+    let greeting = setup.greeting();
+    let authority_keypair = Keypair::new();
+    let authority = authority_keypair.pubkey();
+    setup.account(authority, vec![], &system_program::id());
+    let (vault, _) = VaultSeeds::new(&greeting, &authority).pda(&program_id);
+    let record = Vault {
+        greeting,
+        authority,
+        target: 2,
+    };
+    setup.account(vault, record.try_to_vec().unwrap(), &program_id);
+    let mut harness = setup.start().await;
+
+    let deposit = instruction::deposit(&program_id, &greeting, &authority, 5_000, 2);
+    let withdraw = instruction::withdraw(&program_id, &greeting, &authority);
+    harness
+        .send_ixs(&[deposit], &[&authority_keypair])
+        .await
+        .unwrap();
+    assert_eq!(harness.account(vault).await.lamports, 1_005_000);
+    assert!(harness
+        .send_ixs(&[withdraw.clone()], &[&authority_keypair])
+        .await
+        .is_err());
+    harness
+        .send_ix(instruction::increment(&program_id, &greeting))
+        .await
+        .unwrap();
+    assert!(harness
+        .send_ixs(&[withdraw.clone()], &[&authority_keypair])
+        .await
+        .is_err());
+
+    let set = instruction::set(&program_id, &greeting, 2);
+    harness
+        .send_ixs(&[set, withdraw], &[&authority_keypair])
+        .await
+        .unwrap();
+    // the payer pays the fees, so the authority is back to its starting
+    // balance plus the vault's rent
+    assert_eq!(harness.account(authority).await.lamports, 2_000_000);
+    let closed = harness.context.banks_client.get_account(vault).await;
+    assert_eq!(closed.unwrap(), None);
+}
+
+// CheckIn across epochs, warping to the first slot of each. The streak
+// account is made up front, as the native processor can't create it.
+#[tokio::test]
+async fn test_check_in_streaks() {
+    let mut setup = Setup::new();
+    let program_id = setup.program_id;
+    let greeting = setup.greeting();
+    let user = Keypair::new();
+    setup.account(user.pubkey(), vec![], &system_program::id());
+    let (streak, _) = StreakSeeds::new(&greeting, &user.pubkey()).pda(&program_id);
+    let record = Streak::new(greeting, user.pubkey());
+    setup.account(streak, record.try_to_vec().unwrap(), &program_id);
+    let mut harness = setup.start().await;
+    let epoch_schedule = harness.context.genesis_config().epoch_schedule;
+
+    // epochs 1 and 2 in a row, twice in epoch 2, then a gap before 4
+    let mut warped_to = 0;
+    for (epoch, current, best) in [(1, 1, 1), (2, 2, 2), (2, 2, 2), (4, 1, 2)].iter() {
+        if *epoch != warped_to {
+            harness.warp_to_slot(epoch_schedule.get_first_slot_in_epoch(*epoch));
+            warped_to = *epoch;
+        }
+        let check_in = instruction::check_in(&program_id, &greeting, &user.pubkey());
+        harness.send_ixs(&[check_in], &[&user]).await.unwrap();
+
+        let record = harness.read_state::<Streak>(streak).await;
+        assert_eq!(record.last_epoch, *epoch);
+        assert_eq!(record.current_streak, *current);
+        assert_eq!(record.best_streak, *best);
+    }
+    // every check-in greets, streak or not
+    assert_eq!(harness.read_counter(greeting).await, 4);
+}
+
+// ProposeSet writes into a pending account made up front, as the native
+// processor can't create it. The value only lands with a ConfirmSet
+// config::SET_CONFIRM_DELAY slots later, which also closes the pending
+// account.
+#[tokio::test]
+async fn test_two_phase_set() {
+    let mut setup = Setup::new();
+    let program_id = setup.program_id;
+    let greeting = Pubkey::new_unique();
+    let counter = SET_CONFIRM_THRESHOLD + 1;
+    setup.account(greeting, counter.to_le_bytes().to_vec(), &program_id);
+    let (pending, _) = PendingSetSeeds::new(&greeting).pda(&program_id);
+    setup.account(pending, vec![0; PendingSet::LEN], &program_id);
+    // not the fee payer, so only the refund changes their balance
+    let proposer = Keypair::new();
+    setup.account(proposer.pubkey(), vec![], &system_program::id());
+    let other = Keypair::new();
+    setup.account(other.pubkey(), vec![], &system_program::id());
+    let mut harness = setup.start().await;
+
+    assert!(harness
+        .send_ix(instruction::set(&program_id, &greeting, 7))
+        .await
+        .is_err());
+    let propose = instruction::propose_set(&program_id, &greeting, &proposer.pubkey(), 7);
+    harness.send_ixs(&[propose], &[&proposer]).await.unwrap();
+    let proposed_at = harness.read_state::<PendingSet>(pending).await.slot;
+
+    // someone else can't take over the pending Set and its rent
+    let replace = instruction::propose_set(&program_id, &greeting, &other.pubkey(), 8);
+    assert!(harness.send_ixs(&[replace], &[&other]).await.is_err());
+    let recorded = harness.read_state::<PendingSet>(pending).await;
+    assert_eq!((recorded.proposer, recorded.value), (proposer.pubkey(), 7));
+
+    let confirm = instruction::confirm_set(&program_id, &greeting, &proposer.pubkey());
+    assert!(harness.send_ix(confirm.clone()).await.is_err());
+    assert_eq!(harness.read_counter(greeting).await, counter);
+
+    let rent = harness.account(pending).await.lamports;
+    let balance = harness.account(proposer.pubkey()).await.lamports;
+    harness.warp_to_slot(proposed_at + config::SET_CONFIRM_DELAY);
+    harness.send_ix(confirm).await.unwrap();
+    assert_eq!(harness.read_counter(greeting).await, 7);
+    assert_eq!(
+        harness.account(proposer.pubkey()).await.lamports,
+        balance + rent
+    );
+    let closed = harness.context.banks_client.get_account(pending).await;
+    assert_eq!(closed.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_cancel_set() {
+    let mut setup = Setup::new();
+    let program_id = setup.program_id;
+    let greeting = Pubkey::new_unique();
+    let counter = SET_CONFIRM_THRESHOLD + 1;
+    setup.account(greeting, counter.to_le_bytes().to_vec(), &program_id);
+    let (pending, _) = PendingSetSeeds::new(&greeting).pda(&program_id);
+    setup.account(pending, vec![0; PendingSet::LEN], &program_id);
+    let proposer = Keypair::new();
+    setup.account(proposer.pubkey(), vec![], &system_program::id());
+    let other = Keypair::new();
+    setup.account(other.pubkey(), vec![], &system_program::id());
+    let mut harness = setup.start().await;
+
+    // nothing is pending yet
+    let cancel = instruction::cancel_set(&program_id, &greeting, &proposer.pubkey());
+    assert!(harness
+        .send_ixs(&[cancel.clone()], &[&proposer])
+        .await
+        .is_err());
+    let propose = instruction::propose_set(&program_id, &greeting, &proposer.pubkey(), 7);
+    harness.send_ixs(&[propose], &[&proposer]).await.unwrap();
+
+    let cancel_other = instruction::cancel_set(&program_id, &greeting, &other.pubkey());
+    assert!(harness.send_ixs(&[cancel_other], &[&other]).await.is_err());
+
+    // no need to wait out the delay
+    let rent = harness.account(pending).await.lamports;
+    let balance = harness.account(proposer.pubkey()).await.lamports;
+    harness.send_ixs(&[cancel], &[&proposer]).await.unwrap();
+    assert_eq!(harness.read_counter(greeting).await, counter);
+    assert_eq!(
+        harness.account(proposer.pubkey()).await.lamports,
+        balance + rent
+    );
+    let closed = harness.context.banks_client.get_account(pending).await;
+    assert_eq!(closed.unwrap(), None);
+
+    let confirm = instruction::confirm_set(&program_id, &greeting, &proposer.pubkey());
+    harness.warp_to_slot(config::SET_CONFIRM_DELAY + 100);
+    assert!(harness.send_ix(confirm).await.is_err());
+    assert_eq!(harness.read_counter(greeting).await, counter);
+}
+
+// The allowlist is made up front, with no root yet, as the native processor
+// can't create it. Only the root goes on-chain however many members there
+// are, the proof for one of 1000 is 10 hashes.
+#[tokio::test]
+async fn test_allowlisted_increment() {
+    let mut setup = Setup::new();
+    let program_id = setup.program_id;
+    let greeting = Pubkey::new_unique();
+    setup.account(greeting, 0_u32.to_le_bytes().to_vec(), &program_id);
+    let member = Keypair::new();
+    setup.account(member.pubkey(), vec![], &system_program::id());
+    let (allowlist, _) = AllowlistSeeds::new(&greeting).pda(&program_id);
+    // the member is also the authority
+    let record = Allowlist {
+        greeting,
+        authority: member.pubkey(),
+        root: [0; 32],
+    };
+    setup.account(allowlist, record.try_to_vec().unwrap(), &program_id);
+    let mut harness = setup.start().await;
+
+    let mut members: Vec<Pubkey> = (0..1000).map(|_| Pubkey::new_unique()).collect();
+    members[617] = member.pubkey();
+    let root = merkle::root(&members);
+    let set = instruction::set_allowlist(&program_id, &greeting, &member.pubkey(), root);
+    harness.send_ixs(&[set], &[&member]).await.unwrap();
+    assert_eq!(harness.read_state::<Allowlist>(allowlist).await.root, root);
+
+    let proof = merkle::proof(&members, 617).unwrap();
+    assert_eq!(proof.len(), 10);
+    let increment =
+        instruction::allowlisted_increment(&program_id, &greeting, &member.pubkey(), proof);
+    harness.send_ixs(&[increment], &[&member]).await.unwrap();
+    assert_eq!(harness.read_counter(greeting).await, 1);
+
+    // someone else's proof doesn't work for the member
+    let proof = merkle::proof(&members, 0).unwrap();
+    let increment =
+        instruction::allowlisted_increment(&program_id, &greeting, &member.pubkey(), proof);
+    assert!(harness.send_ixs(&[increment], &[&member]).await.is_err());
+    assert_eq!(harness.read_counter(greeting).await, 1);
+}
+
+// GreeterStats is the largest account the program has, made up front as
+// the native processor can't create it. Under `cargo test-bpf` this also
+// checks that SignedIncrement stays within the on-chain stack frames.
+#[tokio::test]
+async fn test_signed_increment_greeter_stats() {
+    let mut setup = Setup::new();
+    let program_id = setup.program_id;
+    let greeting = Pubkey::new_unique();
+    setup.account(greeting, 0_u32.to_le_bytes().to_vec(), &program_id);
+    // fixed keys, random ones now and then share a register of the sketch
+    let greeters: Vec<Keypair> = (0..8)
+        .map(|i| keypair_from_seed(&[i; 32]).unwrap())
+        .collect();
+    for greeter in &greeters {
+        setup.account(greeter.pubkey(), vec![], &system_program::id());
+    }
+    let (stats, _) = GreeterStatsSeeds::new(&greeting).pda(&program_id);
+    let record = GreeterStats::new(greeting);
+    setup.account(stats, record.try_to_vec().unwrap(), &program_id);
+    let mut harness = setup.start().await;
+
+    // every greeter twice
+    for greeter in greeters.iter().chain(&greeters) {
+        let increment = instruction::signed_increment(&program_id, &greeting, &greeter.pubkey());
+        harness.send_ixs(&[increment], &[greeter]).await.unwrap();
+    }
+    assert_eq!(harness.read_counter(greeting).await, 16);
+    let record = harness.read_state::<GreeterStats>(stats).await;
+    assert_eq!(record.greeting, greeting);
+    assert_eq!(record.unique_greeters_estimate, 8);
+    assert_eq!(record.unique_greeters_hll(), 8);
+}
+
+// program-test deploys with the non-upgradeable loader, so the ProgramData
+// account is made up by hand, encoded the way the upgradeable loader does
+async fn check_upgrade_authority(authority: Option<Pubkey>, strict: bool) -> bool {
+    let mut setup = Setup::new();
+    let program_id = setup.program_id;
+    let state = UpgradeableLoaderState::ProgramData {
+        slot: 1,
+        upgrade_authority_address: authority,
+    };
+    setup.account(
+        seeds::program_data_address(&program_id),
+        bincode::serialize(&state).unwrap(),
+        &bpf_loader_upgradeable::id(),
+    );
+    let mut harness = setup.start().await;
+    harness
+        .send_ix(instruction::check_upgrade_authority(&program_id, strict))
+        .await
+        .is_ok()
+}
+
+#[tokio::test]
+async fn test_check_upgrade_authority() {
+    let expected = config::upgrade_authority::id();
+    assert!(check_upgrade_authority(Some(expected), true).await);
+    assert!(!check_upgrade_authority(Some(Pubkey::new_unique()), true).await);
+    assert!(!check_upgrade_authority(None, true).await);
+    // without strict it only logs
+    assert!(check_upgrade_authority(Some(Pubkey::new_unique()), false).await);
+}
+
+// Compute unit ceilings. The native processor doesn't meter compute, so
+// these only mean something under `cargo test-bpf`, where a transaction
+// that goes over the budget set with set_bpf_compute_max_units() fails.
+#[cfg(feature = "test-bpf")]
+async fn assert_under_compute_units(max_units: u64, make_ix: fn(&Pubkey, &Pubkey) -> Instruction) {
+    let mut setup = Setup::new();
+    setup.program_test.set_bpf_compute_max_units(max_units);
+    let program_id = setup.program_id;
+    let greeting = Pubkey::new_unique();
+    setup.account(greeting, 1_u32.to_le_bytes().to_vec(), &program_id);
+    let mut harness = setup.start().await;
+
+    harness
+        .send_ix(make_ix(&program_id, &greeting))
+        .await
+        .unwrap_or_else(|err| panic!("over {} compute units: {:?}", max_units, err));
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_compute_unit_ceilings() {
+    assert_under_compute_units(5_000, instruction::increment).await;
+    assert_under_compute_units(5_000, instruction::decrement).await;
+    assert_under_compute_units(5_000, |program_id, greeting| {
+        instruction::set(program_id, greeting, 42)
+    })
+    .await;
+    assert_under_compute_units(10_000, |program_id, _| instruction::get_version(program_id)).await;
+    // every operation is a CPI back into the program
+    assert_under_compute_units(25_000, |program_id, greeting| {
+        instruction::batch(
+            program_id,
+            greeting,
+            vec![
+                HelloInstruction::Increment,
+                HelloInstruction::Increment,
+                HelloInstruction::Decrement,
+            ],
+        )
+    })
+    .await;
+}
+
+// A custom-heap build allocates from the top of config::HEAP_FRAME_LEN, past
+// the 32 KiB the runtime maps by default, so anything that allocates only
+// runs in a transaction with the heap request. Batch allocates its ops. The
+// native processor uses the host allocator, so this needs `cargo test-bpf`.
+#[cfg(all(feature = "test-bpf", feature = "custom-heap"))]
+#[tokio::test]
+async fn test_custom_heap_needs_heap_frame() {
+    let mut setup = Setup::new();
+    let greeting = setup.greeting();
+    let mut harness = setup.start().await;
+    let program_id = harness.program_id;
+    let batch = instruction::batch(
+        &program_id,
+        &greeting,
+        vec![HelloInstruction::Increment, HelloInstruction::Increment],
+    );
+
+    // the harness adds the request
+    harness.send_ix(batch.clone()).await.unwrap();
+    assert_eq!(harness.read_counter(greeting).await, 2);
+
+    // without it the first allocation is out of bounds
+    assert!(harness.send_raw_ixs(&[batch], &[]).await.is_err());
+    assert_eq!(harness.read_counter(greeting).await, 2);
+}
+
+// A `require-memo` build only adds to a counter in a transaction with a
+// memo, CPIs included: Batch and MirrorIncrement pass the instructions
+// sysvar on. The harness adds the memo and the sysvar, send_raw_ixs()
+// leaves them out.
+#[cfg(feature = "require-memo")]
+#[tokio::test]
+async fn test_increments_require_memo() {
+    let mirror_program_id = config::mirror::id();
+    let mirror_greeting = Pubkey::new_unique();
+    let mut setup = Setup::new();
+    let program_id = setup.program_id;
+    setup.program_test.add_program(
+        "helloworld",
+        mirror_program_id,
+        processor!(process_instruction),
+    );
+    let greeting = setup.greeting();
+    setup.account(
+        mirror_greeting,
+        vec![0; GreetingAccount::LEN],
+        &mirror_program_id,
+    );
+    let greeter = Keypair::new();
+    setup.account(greeter.pubkey(), vec![], &system_program::id());
+    let (stats, _) = GreeterStatsSeeds::new(&greeting).pda(&program_id);
+    let record = GreeterStats::new(greeting);
+    setup.account(stats, record.try_to_vec().unwrap(), &program_id);
+    let mut harness = setup.start().await;
+
+    let increments = vec![
+        (instruction::increment(&program_id, &greeting), vec![]),
+        (
+            instruction::batch(&program_id, &greeting, vec![HelloInstruction::Increment]),
+            vec![],
+        ),
+        (
+            instruction::mirror_increment(&program_id, &greeting, &mirror_greeting),
+            vec![],
+        ),
+        (
+            instruction::signed_increment(&program_id, &greeting, &greeter.pubkey()),
+            vec![&greeter],
+        ),
+    ];
+    for (count, (ix, signers)) in increments.into_iter().enumerate() {
+        let bare = harness.send_raw_ixs(&[ix.clone()], &signers).await;
+        assert_eq!(
+            bare.unwrap_err().unwrap(),
+            TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)
+        );
+        let with_sysvar = instruction::with_instructions_sysvar(ix.clone());
+        let no_memo = harness.send_raw_ixs(&[with_sysvar], &signers).await;
+        assert_eq!(
+            no_memo.unwrap_err().unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(HelloError::MissingMemo as u32)
+            )
+        );
+        harness.send_ixs(&[ix], &signers).await.unwrap();
+        assert_eq!(harness.read_counter(greeting).await, count as u32 + 1);
+    }
+    assert_eq!(harness.read_counter(mirror_greeting).await, 1);
+}
@@ -0,0 +1,101 @@
+// End to end check of the quickstart against a real validator: start
+// solana-test-validator, deploy dist/program/helloworld.so and run the
+// TypeScript client (`npm run start`) against it.
+//
+// It needs the Solana CLI tools and node on PATH, the program built with
+// `npm run build:program-rust`, `npm install` done and the CLI config
+// pointing at localhost (`solana config set --url localhost`), so it is
+// ignored by default. Run it with
+//
+//     cargo test --test localnet -- --ignored
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Child, Command, Output, Stdio},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+const RPC_URL: &str = "http://127.0.0.1:8899";
+
+// Kills the validator when the test ends, pass or fail
+struct Validator(Child);
+
+impl Drop for Validator {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn repo_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+fn run(command: &mut Command) -> Output {
+    let output = command.output().expect("failed to spawn");
+    assert!(
+        output.status.success(),
+        "{:?} failed\nstdout:\n{}\nstderr:\n{}",
+        command,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+fn start_validator() -> Validator {
+    let ledger = std::env::temp_dir().join("helloworld-localnet-ledger");
+    let child = Command::new("solana-test-validator")
+        .arg("--reset")
+        .arg("--quiet")
+        .arg("--ledger")
+        .arg(&ledger)
+        .stdout(Stdio::null())
+        .spawn()
+        .expect("solana-test-validator not found");
+    let validator = Validator(child);
+
+    let deadline = Instant::now() + Duration::from_secs(60);
+    loop {
+        let ready = Command::new("solana")
+            .args(&["cluster-version", "--url", RPC_URL])
+            .output()
+            .map(|output| output.status.success())
+            .unwrap_or(false);
+        if ready {
+            return validator;
+        }
+        assert!(Instant::now() < deadline, "validator didn't start");
+        sleep(Duration::from_millis(500));
+    }
+}
+
+#[test]
+#[ignore]
+fn test_deploy_and_greet_on_localnet() {
+    let root = repo_root();
+    let program = root.join("dist/program/helloworld.so");
+    let program_keypair = root.join("dist/program/helloworld-keypair.json");
+    assert!(
+        program.exists(),
+        "{} is missing, run `npm run build:program-rust`",
+        program.display()
+    );
+
+    let _validator = start_validator();
+    run(Command::new("solana").args(&["airdrop", "10", "--url", RPC_URL]));
+    run(Command::new("solana")
+        .args(&["program", "deploy", "--url", RPC_URL, "--program-id"])
+        .arg(&program_keypair)
+        .arg(&program));
+
+    // the client greets the account it derives from the payer once and
+    // prints Success when every step went through
+    let output = run(Command::new("npm")
+        .args(&["run", "start"])
+        .current_dir(&root));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("has been greeted"), "{}", stdout);
+    assert!(stdout.contains("Success"), "{}", stdout);
+}
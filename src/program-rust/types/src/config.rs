@@ -0,0 +1,112 @@
+//src/program-rust/types/src/config.rs
+// Addresses this program is configured to talk to and the settings it is
+// built with, fixed at build time
+
+/// The second deployment of the counter program that MirrorIncrement
+/// forwards to. Deploy the same helloworld.so at this address (or change it
+/// here and rebuild) to use the instruction.
+pub mod mirror {
+    solana_program::declare_id!("FfUYmFpgbfuTC9SdjB9LvZaQKG5zHV6rTekNjpM2vju3");
+}
+
+/// The program whose GreetingAccount-shaped accounts CopyFrom is allowed to
+/// read. Only accounts owned by this program are trusted to have that layout.
+pub mod foreign {
+    solana_program::declare_id!("3o2PnkYZPnw6SLuskLHnJgrGfHbbekFJDBXkJBCeTtiR");
+}
+
+/// The SPL Memo program. A build with the `require-memo` feature only runs
+/// Increment in a transaction that also calls it (or memo_v1).
+pub mod memo {
+    solana_program::declare_id!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+}
+
+/// The key expected to hold the program's upgrade authority, which
+/// CheckUpgradeAuthority compares the deployed one with. Change it here
+/// and rebuild to the deployer's key (or multisig).
+pub mod upgrade_authority {
+    solana_program::declare_id!("Ahi6Q9SvvNQTBuriNpfCDhYUKqgypeYQZtJCpCyVrkzK");
+}
+
+/// The first deployment of the SPL Memo program, still accepted
+pub mod memo_v1 {
+    solana_program::declare_id!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo");
+}
+
+/// The heap a `custom-heap` build of the program allocates from. The
+/// runtime only maps more than the default 32 KiB for a transaction that
+/// requests it, see hello_client::with_heap_frame().
+pub const HEAP_FRAME_LEN: usize = 256 * 1024;
+
+/// Slots that have to pass between a ProposeSet and the ConfirmSet that
+/// applies it. Change it here and rebuild for a longer or shorter window
+/// to spot a wrong value in.
+pub const SET_CONFIRM_DELAY: u64 = 10;
+
+/// The cluster a build is for, see cluster()
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Cluster {
+    Devnet,
+    Testnet,
+    Mainnet,
+    /// A local solana-test-validator, what a build without a cluster
+    /// feature is for
+    Localnet,
+}
+
+impl Cluster {
+    /// Name of the cluster as the Solana CLI and the TypeScript client's
+    /// cluster-*.env files spell it
+    pub fn name(self) -> &'static str {
+        match self {
+            Cluster::Devnet => "devnet",
+            Cluster::Testnet => "testnet",
+            Cluster::Mainnet => "mainnet-beta",
+            Cluster::Localnet => "localnet",
+        }
+    }
+
+    /// Default JSON RPC endpoint of the cluster
+    pub fn url(self) -> &'static str {
+        match self {
+            Cluster::Devnet => "https://api.devnet.solana.com",
+            Cluster::Testnet => "https://api.testnet.solana.com",
+            Cluster::Mainnet => "https://api.mainnet-beta.solana.com",
+            Cluster::Localnet => "http://127.0.0.1:8899",
+        }
+    }
+}
+
+#[cfg(any(
+    all(feature = "devnet", feature = "testnet"),
+    all(feature = "devnet", feature = "mainnet"),
+    all(feature = "testnet", feature = "mainnet"),
+))]
+compile_error!("enable at most one of the devnet, testnet and mainnet features");
+
+/// The cluster picked with the devnet, testnet or mainnet feature
+pub const fn cluster() -> Cluster {
+    if cfg!(feature = "devnet") {
+        Cluster::Devnet
+    } else if cfg!(feature = "testnet") {
+        Cluster::Testnet
+    } else if cfg!(feature = "mainnet") {
+        Cluster::Mainnet
+    } else {
+        Cluster::Localnet
+    }
+}
+
+/// Where this program is deployed on cluster(), for clients to send its
+/// instructions to. Deploy helloworld.so at the address for the cluster (or
+/// change it here and rebuild).
+pub mod program {
+    #[cfg(feature = "devnet")]
+    solana_program::declare_id!("4VzUbyPiMdJTBJuggoh22GKBeNY5WrHe7wfPpU49c8mQ");
+    #[cfg(feature = "testnet")]
+    solana_program::declare_id!("6xHh6hWBwFC9s37PouPWKnaEUjDGzSVc2Dn88uiY5cHb");
+    #[cfg(feature = "mainnet")]
+    solana_program::declare_id!("GGnQXNiB2qc6LvnxC2xNpoPuUjER8xiMu19ocUMVgXBQ");
+    #[cfg(not(any(feature = "devnet", feature = "testnet", feature = "mainnet")))]
+    solana_program::declare_id!("7oPC1GwATm2TS5bMiqHytjuiPxwNrT4hyDncNR3NtsVJ");
+}
@@ -0,0 +1,224 @@
+//src/program-rust/types/src/discriminator.rs
+// Anchor style instruction discriminators
+//
+// Anchor clients start instruction data with the first 8 bytes of
+// sha256("global:<instruction name>") followed by the Borsh encoded
+// arguments. For the instructions listed here the Borsh encoding of the
+// arguments is byte for byte the same as what follows the tag in
+// HelloInstruction::unpack, so translating is only a matter of swapping the
+// discriminator for the tag. Batch, CreatePoll, SetUri, Idempotent and
+// AllowlistedIncrement length-prefix their arguments with a u8 where Borsh
+// uses a u32 and aren't accepted in this form.
+//
+// The discriminators are written out instead of hashed so the program
+// doesn't spend a sha256 per entry on every instruction.
+// test_discriminators_are_sighashes checks them.
+//
+// Telling the two formats apart: data is Anchor style when its first 8
+// bytes are one of the discriminators, anything else is a tag. Two of the
+// discriminators start with a tag (increment with 11, ClosePoll, and
+// copy_from with 6, CopyFrom), but those instructions take no arguments and
+// unpack() rejects trailing bytes, so no data is valid in both formats.
+
+use crate::instruction::HelloInstruction;
+use solana_program::program_error::ProgramError;
+
+/// Length of an Anchor discriminator
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+/// Anchor instruction name, HelloInstruction tag and discriminator
+pub const ANCHOR_INSTRUCTIONS: &[(&str, u8, [u8; DISCRIMINATOR_LEN])] = &[
+    ("increment", 0, [11, 18, 104, 9, 104, 174, 59, 33]),
+    ("decrement", 1, [106, 227, 168, 59, 248, 27, 150, 101]),
+    ("set", 2, [198, 51, 53, 241, 116, 29, 126, 194]),
+    ("get_version", 3, [168, 85, 244, 45, 81, 56, 130, 50]),
+    ("mirror_increment", 5, [213, 97, 62, 44, 187, 43, 156, 249]),
+    ("copy_from", 6, [6, 246, 242, 7, 177, 113, 45, 8]),
+    ("upvote", 7, [197, 8, 144, 51, 126, 41, 156, 81]),
+    ("downvote", 8, [73, 64, 0, 158, 133, 185, 55, 7]),
+    ("vote", 10, [227, 110, 155, 23, 136, 126, 172, 25]),
+    ("close_poll", 11, [139, 213, 162, 65, 172, 150, 123, 67]),
+    ("attest", 12, [83, 148, 120, 119, 144, 139, 117, 160]),
+    ("set_language", 14, [222, 147, 20, 91, 180, 156, 247, 185]),
+    ("deposit", 16, [242, 35, 198, 137, 82, 225, 242, 182]),
+    ("withdraw", 17, [183, 18, 70, 156, 148, 109, 161, 34]),
+    ("claim_badge", 18, [111, 30, 18, 17, 228, 252, 239, 102]),
+    ("check_in", 19, [209, 253, 4, 217, 250, 241, 207, 50]),
+    (
+        "check_upgrade_authority",
+        20,
+        [118, 3, 123, 184, 253, 254, 100, 170],
+    ),
+    ("propose_set", 21, [105, 30, 114, 149, 169, 231, 185, 13]),
+    ("confirm_set", 22, [229, 100, 164, 209, 205, 148, 190, 90]),
+    ("set_allowlist", 23, [141, 30, 41, 131, 132, 7, 216, 134]),
+    ("signed_increment", 25, [167, 55, 48, 41, 64, 215, 155, 77]),
+    ("get_multiple", 26, [210, 117, 169, 115, 163, 111, 253, 150]),
+    ("cancel_set", 27, [126, 166, 199, 90, 119, 225, 180, 92]),
+];
+
+/// How instruction data is encoded
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataFormat {
+    /// A HelloInstruction tag followed by its arguments, what pack() writes
+    Tag,
+    /// One of ANCHOR_INSTRUCTIONS followed by the Borsh encoded arguments
+    Anchor,
+}
+
+/// Which format `data` is in, see the top of this file
+pub fn detect(data: &[u8]) -> DataFormat {
+    if find(data).is_some() {
+        DataFormat::Anchor
+    } else {
+        DataFormat::Tag
+    }
+}
+
+/// Decode data in either format
+pub fn decode(data: &[u8]) -> Result<HelloInstruction, ProgramError> {
+    match from_anchor(data) {
+        Some(legacy) => HelloInstruction::unpack(&legacy),
+        None => HelloInstruction::unpack(data),
+    }
+}
+
+// The entry of ANCHOR_INSTRUCTIONS `data` starts with
+fn find(data: &[u8]) -> Option<&'static (&'static str, u8, [u8; DISCRIMINATOR_LEN])> {
+    let discriminator = data.get(..DISCRIMINATOR_LEN)?;
+    ANCHOR_INSTRUCTIONS
+        .iter()
+        .find(|(_, _, known)| known[..] == *discriminator)
+}
+
+/// Rewrite Anchor style instruction data into the tag encoding unpack()
+/// reads. None unless it starts with one of ANCHOR_INSTRUCTIONS.
+pub fn from_anchor(data: &[u8]) -> Option<Vec<u8>> {
+    let (_, tag, _) = find(data)?;
+    let args = &data[DISCRIMINATOR_LEN..];
+    let mut legacy = Vec::with_capacity(1 + args.len());
+    legacy.push(*tag);
+    legacy.extend_from_slice(args);
+    Some(legacy)
+}
+
+/// The opposite of from_anchor(), for clients that want to send the Anchor
+/// form. None for tags without a discriminator.
+pub fn to_anchor(data: &[u8]) -> Option<Vec<u8>> {
+    let (&tag, args) = data.split_first()?;
+    let (_, _, discriminator) = ANCHOR_INSTRUCTIONS
+        .iter()
+        .find(|(_, known, _)| *known == tag)?;
+    let mut anchor = discriminator.to_vec();
+    anchor.extend_from_slice(args);
+    Some(anchor)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::HelloError;
+    use solana_program::hash::hashv;
+
+    #[test]
+    fn test_discriminators_are_sighashes() {
+        for (name, tag, discriminator) in ANCHOR_INSTRUCTIONS {
+            let sighash = hashv(&[b"global:", name.as_bytes()]).to_bytes();
+            assert_eq!(&sighash[..DISCRIMINATOR_LEN], discriminator, "{}", name);
+            // no tag is listed twice
+            let same_tag = ANCHOR_INSTRUCTIONS.iter().filter(|(_, t, _)| t == tag);
+            assert_eq!(same_tag.count(), 1, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_anchor_round_trip() {
+        let instructions = vec![
+            HelloInstruction::Increment,
+            HelloInstruction::Set(7),
+            HelloInstruction::Vote(3),
+            HelloInstruction::Attest([9; 32]),
+            HelloInstruction::Deposit(1_000, 5),
+            HelloInstruction::CheckUpgradeAuthority(true),
+            HelloInstruction::SetAllowlist([1; 32]),
+        ];
+        for instruction in instructions {
+            let anchor = to_anchor(&instruction.pack()).unwrap();
+            let legacy = from_anchor(&anchor).unwrap();
+            assert_eq!(HelloInstruction::unpack(&legacy), Ok(instruction));
+        }
+
+        // Set(7) as an Anchor client built with `fn set(value: u32)` sends it
+        let mut set = vec![198, 51, 53, 241, 116, 29, 126, 194];
+        set.extend_from_slice(&7u32.to_le_bytes());
+        assert_eq!(from_anchor(&set), Some(vec![2, 7, 0, 0, 0]));
+
+        assert_eq!(
+            to_anchor(&HelloInstruction::SetUri("x".into()).pack()),
+            None
+        );
+        assert_eq!(from_anchor(&[0; DISCRIMINATOR_LEN]), None);
+        assert_eq!(from_anchor(&[11, 18, 104]), None);
+    }
+
+    #[test]
+    fn test_ambiguous_prefixes() {
+        // the discriminators that start with a tag
+        let tagged: Vec<_> = ANCHOR_INSTRUCTIONS
+            .iter()
+            .filter(|(_, _, discriminator)| HelloInstruction::unpack(&discriminator[..1]).is_ok())
+            .map(|(name, _, _)| *name)
+            .collect();
+        assert_eq!(tagged, ["increment", "copy_from"]);
+
+        // the whole discriminator decides, so increment's isn't a ClosePoll
+        let increment = [11, 18, 104, 9, 104, 174, 59, 33];
+        assert_eq!(detect(&increment), DataFormat::Anchor);
+        assert_eq!(decode(&increment), Ok(HelloInstruction::Increment));
+        // while a ClosePoll is read as one, and a partial or wrong
+        // discriminator after it as trailing bytes
+        assert_eq!(decode(&[11]), Ok(HelloInstruction::ClosePoll));
+        for close_poll in &[&[11, 18, 104][..], &[11, 18, 104, 9, 104, 174, 59, 34]] {
+            assert_eq!(detect(close_poll), DataFormat::Tag);
+            assert_eq!(
+                decode(close_poll),
+                Err(HelloError::TrailingInstructionData.into())
+            );
+        }
+        // copy_from means CopyFrom either way
+        assert_eq!(
+            decode(&[6, 246, 242, 7, 177, 113, 45, 8]),
+            Ok(HelloInstruction::CopyFrom)
+        );
+        assert_eq!(decode(&[6]), Ok(HelloInstruction::CopyFrom));
+
+        // every instruction the builders can produce keeps its tag reading
+        let packed = vec![
+            HelloInstruction::Increment,
+            HelloInstruction::ClosePoll,
+            HelloInstruction::CopyFrom,
+            HelloInstruction::Set(u32::from_le_bytes([18, 104, 9, 104])),
+            HelloInstruction::Attest([11; 32]),
+            HelloInstruction::Batch(vec![
+                HelloInstruction::Increment,
+                HelloInstruction::Increment,
+            ]),
+            HelloInstruction::Idempotent([11; 16], Box::new(HelloInstruction::Increment)),
+        ];
+        for instruction in packed {
+            let data = instruction.pack();
+            assert_eq!(detect(&data), DataFormat::Tag, "{:?}", instruction);
+            assert_eq!(decode(&data), Ok(instruction));
+        }
+
+        // a discriminator with bad arguments is an error, not a tag
+        let mut short_set = vec![198, 51, 53, 241, 116, 29, 126, 194];
+        short_set.push(7);
+        assert_eq!(detect(&short_set), DataFormat::Anchor);
+        assert_eq!(
+            decode(&short_set),
+            Err(HelloError::InvalidInstruction.into())
+        );
+        assert_eq!(decode(&[]), Err(HelloError::InvalidInstruction.into()));
+    }
+}
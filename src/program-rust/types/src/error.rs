@@ -0,0 +1,151 @@
+//src/program-rust/types/src/error.rs
+// Errors returned by the hello world program.
+//
+// A panic on-chain aborts the program, burns the whole compute budget and
+// only leaves the client with a generic "program failed" message. Code paths
+// that could panic (slice indexing, counter arithmetic) return one of these
+// errors instead, which the client receives as ProgramError::Custom(code).
+
+use solana_program::{decode_error::DecodeError, program_error::ProgramError};
+use thiserror::Error;
+
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum HelloError {
+    /// The instruction data could not be decoded
+    #[error("Invalid instruction")]
+    InvalidInstruction,
+    /// Incrementing the counter would go past u32::MAX
+    #[error("Counter overflow")]
+    CounterOverflow,
+    /// Decrementing the counter would go below zero
+    #[error("Counter underflow")]
+    CounterUnderflow,
+    /// More accounts were passed than the instruction takes
+    #[error("Too many accounts")]
+    TooManyAccounts,
+    /// The same account was passed in two writable positions
+    #[error("Duplicate account")]
+    DuplicateAccount,
+    /// The voter already has a receipt for this account
+    #[error("Already voted")]
+    AlreadyVoted,
+    /// The signer is not the authority of the account
+    #[error("Unauthorized")]
+    Unauthorized,
+    /// Vote was given an option index the poll doesn't have
+    #[error("Invalid poll option")]
+    InvalidPollOption,
+    /// The poll was closed by its authority
+    #[error("Poll is closed")]
+    PollClosed,
+    /// SetLanguage was given a code that isn't in locale::SUPPORTED
+    #[error("Unsupported language")]
+    UnsupportedLanguage,
+    /// The op_id is in the greeting's window of recent operations
+    #[error("Duplicate operation")]
+    DuplicateOperation,
+    /// The transaction has no SPL Memo instruction, see `require-memo`
+    #[error("Missing memo")]
+    MissingMemo,
+    /// Withdraw was called before the vault's counter reached its target
+    #[error("Vault is locked")]
+    VaultLocked,
+    /// ClaimBadge was called before the counter reached the milestone
+    #[error("Milestone not reached")]
+    MilestoneNotReached,
+    /// Set on a counter above SET_CONFIRM_THRESHOLD, use ProposeSet and
+    /// ConfirmSet instead
+    #[error("Set needs confirming")]
+    ConfirmationRequired,
+    /// ConfirmSet came less than config::SET_CONFIRM_DELAY slots after
+    /// ProposeSet
+    #[error("Confirmed too early")]
+    ConfirmTooEarly,
+    /// The signer's Merkle proof does not lead to the greeting's allowlist
+    /// root
+    #[error("Not on the allowlist")]
+    NotAllowlisted,
+    /// The instruction data goes on after the instruction's last argument
+    #[error("Trailing instruction data")]
+    TrailingInstructionData,
+}
+
+// lets handlers write `HelloError::X.into()` or use `?` on a HelloError
+impl From<HelloError> for ProgramError {
+    fn from(e: HelloError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+impl<T> DecodeError<T> for HelloError {
+    fn type_of() -> &'static str {
+        "HelloError"
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // The codes clients match on. Append new variants at the end of both the
+    // enum and this table; never renumber an existing entry.
+    const CODES: &[(HelloError, u32)] = &[
+        (HelloError::InvalidInstruction, 0),
+        (HelloError::CounterOverflow, 1),
+        (HelloError::CounterUnderflow, 2),
+        (HelloError::TooManyAccounts, 3),
+        (HelloError::DuplicateAccount, 4),
+        (HelloError::AlreadyVoted, 5),
+        (HelloError::Unauthorized, 6),
+        (HelloError::InvalidPollOption, 7),
+        (HelloError::PollClosed, 8),
+        (HelloError::UnsupportedLanguage, 9),
+        (HelloError::DuplicateOperation, 10),
+        (HelloError::MissingMemo, 11),
+        (HelloError::VaultLocked, 12),
+        (HelloError::MilestoneNotReached, 13),
+        (HelloError::ConfirmationRequired, 14),
+        (HelloError::ConfirmTooEarly, 15),
+        (HelloError::NotAllowlisted, 16),
+        (HelloError::TrailingInstructionData, 17),
+    ];
+
+    // no wildcard arm, so adding a variant fails to compile until it gets a
+    // row in CODES
+    fn row(e: &HelloError) -> usize {
+        match e {
+            HelloError::InvalidInstruction => 0,
+            HelloError::CounterOverflow => 1,
+            HelloError::CounterUnderflow => 2,
+            HelloError::TooManyAccounts => 3,
+            HelloError::DuplicateAccount => 4,
+            HelloError::AlreadyVoted => 5,
+            HelloError::Unauthorized => 6,
+            HelloError::InvalidPollOption => 7,
+            HelloError::PollClosed => 8,
+            HelloError::UnsupportedLanguage => 9,
+            HelloError::DuplicateOperation => 10,
+            HelloError::MissingMemo => 11,
+            HelloError::VaultLocked => 12,
+            HelloError::MilestoneNotReached => 13,
+            HelloError::ConfirmationRequired => 14,
+            HelloError::ConfirmTooEarly => 15,
+            HelloError::NotAllowlisted => 16,
+            HelloError::TrailingInstructionData => 17,
+        }
+    }
+
+    #[test]
+    fn test_error_codes_match_table() {
+        for (i, (error, code)) in CODES.iter().enumerate() {
+            assert_eq!(row(error), i, "{:?} is out of place in CODES", error);
+            assert_eq!(
+                ProgramError::from(error.clone()),
+                ProgramError::Custom(*code),
+                "{:?} changed its error code",
+                error
+            );
+        }
+    }
+}
@@ -0,0 +1,917 @@
+//src/program-rust/types/src/instruction.rs
+// customizing Hello world contract
+
+use crate::error::HelloError;
+use crate::merkle::MAX_PROOF_LEN;
+use crate::seeds::{
+    self, AllowlistSeeds, AttestationSeeds, BadgeSeeds, GreeterStatsSeeds, MetadataSeeds,
+    PendingSetSeeds, RecentOpsSeeds, ReceiptSeeds, StreakSeeds, VaultSeeds,
+};
+use crate::state::{MAX_POLL_LABEL_LEN, MAX_POLL_OPTIONS, MAX_URI_LEN, MILESTONES};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use std::cmp::Ordering;
+use std::convert::TryInto;
+
+/// Largest number of operations a single Batch may carry
+pub const MAX_BATCH_LEN: usize = 16;
+
+/// Most greeting accounts a single GetMultiple may read
+pub const MAX_GET_MULTIPLE_LEN: usize = 32;
+
+// The enum below will be used by the client to send us specific instruction to be
+// executed in the smart contract
+// Increment will increase counter by 1
+// Decrement will decrease counter by 1
+// Set will set the value of the counter to the u32 sent by client
+// GetVersion takes no accounts and returns the program's VersionInfo as
+// return data
+// Batch runs each of its operations, in order, as a CPI of the program into
+// itself. Only Increment, Decrement and Set can be batched
+// MirrorIncrement increments the counter and then the counter of a second
+// greeting account in the mirror deployment from config::mirror
+// CopyFrom overwrites the counter with the one stored in a read-only
+// account of the foreign program from config::foreign
+// Upvote/Downvote increment/decrement the counter once per voter, recorded
+// by a VoteReceipt PDA the voter pays for
+// CreatePoll sets up a Poll account with the given option labels, Vote(i)
+// votes for option i once per voter (with the same receipts as Upvote) and
+// ClosePoll stops the voting
+// Attest(hash) records a 32 byte content hash, the slot and the signer in the
+// greeting's attestation PDA, replacing the previous one
+// SetUri(uri) points the greeting's metadata PDA at an off-chain JSON
+// document, only its authority may change it afterwards
+// SetLanguage(code) sets the language, a locale::SUPPORTED ISO 639-1 code,
+// Increment, Decrement and Set log in when the metadata PDA is passed too
+// Idempotent(op_id, op) runs an Increment, Decrement or Set unless op_id is
+// among the greeting's last RECENT_OPS_LEN op_ids, so a retried transaction
+// is applied once. An all zero op_id isn't allowed
+// Deposit(amount, target) moves amount lamports from the signer into their
+// vault for the greeting, which the first Deposit opens with target as the
+// counter value that unlocks it. Later deposits must give the same target
+// Withdraw closes the signer's vault and returns all of its lamports, once
+// the greeting's counter is at least the target
+// ClaimBadge(milestone) creates the greeting's Badge for the milestone, one
+// of state::MILESTONES, once its counter has reached it. Anyone may claim
+// and pay for it, the badge records the greeting and not the claimer
+// CheckIn increments the counter and extends the signer's Streak on the
+// greeting if they last checked in during the previous epoch
+// CheckUpgradeAuthority(strict) reads the program's ProgramData account and
+// logs its upgrade authority. With strict set it fails unless that is
+// config::upgrade_authority, so a transaction can assert who can upgrade
+// Set fails on a counter above state::SET_CONFIRM_THRESHOLD. ProposeSet(value)
+// records the value instead and ConfirmSet, at least config::SET_CONFIRM_DELAY
+// slots later in another transaction, applies it. Only the proposer of a pending
+// Set can replace it, or CancelSet it to close the pending Set and get its
+// rent back
+// SetAllowlist(root) stores the Merkle root of the wallets allowed to
+// AllowlistedIncrement the greeting. The first call creates the allowlist
+// PDA, only its authority may change the root afterwards
+// AllowlistedIncrement(proof) increments the counter if the proof shows the
+// signer is in the allowlist. Plain Increment is not gated by it
+// SignedIncrement increments the counter and records the signer in the
+// greeting's GreeterStats bloom filter, which estimates unique greeters
+// GetMultiple takes up to MAX_GET_MULTIPLE_LEN greeting accounts, read-only,
+// and returns their counters in account order as a Borsh encoded Vec<u32>
+// in return data
+// Debug macro to print out the enum value
+#[derive(Debug, PartialEq)]
+pub enum HelloInstruction {
+    Increment,
+    Decrement,
+    Set(u32),
+    GetVersion,
+    Batch(Vec<HelloInstruction>),
+    MirrorIncrement,
+    CopyFrom,
+    Upvote,
+    Downvote,
+    CreatePoll(Vec<String>),
+    Vote(u8),
+    ClosePoll,
+    Attest([u8; 32]),
+    SetUri(String),
+    SetLanguage([u8; 2]),
+    Idempotent([u8; 16], Box<HelloInstruction>),
+    Deposit(u64, u32),
+    Withdraw,
+    ClaimBadge(u32),
+    CheckIn,
+    CheckUpgradeAuthority(bool),
+    ProposeSet(u32),
+    ConfirmSet,
+    SetAllowlist([u8; 32]),
+    AllowlistedIncrement(Vec<[u8; 32]>),
+    SignedIncrement,
+    GetMultiple,
+    CancelSet,
+}
+
+
+impl HelloInstruction {
+
+    // implement a unpack function on this enum to take the client buffer and
+    // decode it to the enum above
+    // unpack will return a Self i.e, a HelloInstruction enum
+    // If error, then we will return a solana defined ProgramError
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        // split_first() function on &[u8] gives back an Option enum with value suchh that
+        // first element of u8 is returned. If there is a problem None will be returned
+        // We take the None and convert it to a Result error using ok_or() function.
+        // this gives a result so if successful we will obtain the value by using ?
+        // if there is an error the ? will propagate the error as a return to this 
+        // function
+        let (&tag, rest) = input.split_first().ok_or(HelloError::InvalidInstruction)?;
+
+        // use match to conver the tag number to enum of HelloInstruction
+        match tag {
+            // Ok(T) is the first field of the std library Result<T,E>
+            // enum Result<T, E> {
+            //     Ok(T),
+            //     Err(E),
+            //  }
+            // so below we are returning a Result<T> by wrapping enum with Ok()
+            // Every instruction has to use up all of the data: bytes after
+            // its last field are TrailingInstructionData, so they can't be
+            // given a meaning later without old programs quietly running
+            // the instruction anyway
+            0 => complete(rest, HelloInstruction::Increment),
+            1 => complete(rest, HelloInstruction::Decrement),
+            2 => {
+                // convert rest array slice to a fixed size array. fixed() only
+                // succeeds if rest has exactly 4 elements, so there is no slice
+                // indexing here that could panic on short input.
+                let value: [u8; 4] = fixed(rest)?;
+                Ok(HelloInstruction::Set(u32::from_le_bytes(value)))
+            },
+            3 => complete(rest, HelloInstruction::GetVersion),
+            4 => {
+                // [count: u8] followed by count entries of [len: u8][op bytes]
+                // where each op is itself an encoded HelloInstruction
+                let (&count, mut rest) = rest.split_first().ok_or(HelloError::InvalidInstruction)?;
+                if count as usize > MAX_BATCH_LEN {
+                    return Err(HelloError::InvalidInstruction.into());
+                }
+                let mut ops = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let (&len, tail) = rest.split_first().ok_or(HelloError::InvalidInstruction)?;
+                    if tail.len() < len as usize {
+                        return Err(HelloError::InvalidInstruction.into());
+                    }
+                    let (op, tail) = tail.split_at(len as usize);
+                    let op = HelloInstruction::unpack(op)?;
+                    if !op.is_batchable() {
+                        return Err(HelloError::InvalidInstruction.into());
+                    }
+                    ops.push(op);
+                    rest = tail;
+                }
+                complete(rest, HelloInstruction::Batch(ops))
+            },
+            5 => complete(rest, HelloInstruction::MirrorIncrement),
+            6 => complete(rest, HelloInstruction::CopyFrom),
+            7 => complete(rest, HelloInstruction::Upvote),
+            8 => complete(rest, HelloInstruction::Downvote),
+            9 => {
+                // [count: u8] followed by count labels of [len: u8][utf8 bytes]
+                let (&count, mut rest) = rest.split_first().ok_or(HelloError::InvalidInstruction)?;
+                if count == 0 || count as usize > MAX_POLL_OPTIONS {
+                    return Err(HelloError::InvalidInstruction.into());
+                }
+                let mut labels = Vec::with_capacity(count as usize);
+                for _ in 0..count {
+                    let (&len, tail) = rest.split_first().ok_or(HelloError::InvalidInstruction)?;
+                    if len as usize > MAX_POLL_LABEL_LEN || tail.len() < len as usize {
+                        return Err(HelloError::InvalidInstruction.into());
+                    }
+                    let (label, tail) = tail.split_at(len as usize);
+                    let label = std::str::from_utf8(label).map_err(|_| HelloError::InvalidInstruction)?;
+                    labels.push(label.to_string());
+                    rest = tail;
+                }
+                complete(rest, HelloInstruction::CreatePoll(labels))
+            },
+            10 => {
+                let [index] = fixed(rest)?;
+                Ok(HelloInstruction::Vote(index))
+            },
+            11 => complete(rest, HelloInstruction::ClosePoll),
+            12 => Ok(HelloInstruction::Attest(fixed(rest)?)),
+            13 => {
+                // [len: u8][utf8 bytes], nothing after it
+                let (&len, uri) = rest.split_first().ok_or(HelloError::InvalidInstruction)?;
+                if len as usize > MAX_URI_LEN {
+                    return Err(HelloError::InvalidInstruction.into());
+                }
+                check_len(uri, len as usize)?;
+                let uri = std::str::from_utf8(uri).map_err(|_| HelloError::InvalidInstruction)?;
+                Ok(HelloInstruction::SetUri(uri.to_string()))
+            },
+            14 => Ok(HelloInstruction::SetLanguage(fixed(rest)?)),
+            15 => {
+                // [op_id: 16 bytes][op bytes]
+                if rest.len() < 16 {
+                    return Err(HelloError::InvalidInstruction.into());
+                }
+                let (op_id, op) = rest.split_at(16);
+                let op_id: [u8; 16] = op_id.try_into().map_err(|_| HelloError::InvalidInstruction)?;
+                let op = HelloInstruction::unpack(op)?;
+                if op_id == [0; 16] || !op.is_batchable() {
+                    return Err(HelloError::InvalidInstruction.into());
+                }
+                Ok(HelloInstruction::Idempotent(op_id, Box::new(op)))
+            },
+            16 => {
+                // [amount: u64][target: u32]
+                check_len(rest, 12)?;
+                let (amount, target) = rest.split_at(8);
+                let amount = amount.try_into().map_err(|_| HelloError::InvalidInstruction)?;
+                let target = target.try_into().map_err(|_| HelloError::InvalidInstruction)?;
+                Ok(HelloInstruction::Deposit(
+                    u64::from_le_bytes(amount),
+                    u32::from_le_bytes(target),
+                ))
+            },
+            17 => complete(rest, HelloInstruction::Withdraw),
+            18 => {
+                let milestone = u32::from_le_bytes(fixed(rest)?);
+                if !MILESTONES.contains(&milestone) {
+                    return Err(HelloError::InvalidInstruction.into());
+                }
+                Ok(HelloInstruction::ClaimBadge(milestone))
+            },
+            19 => complete(rest, HelloInstruction::CheckIn),
+            20 => match fixed(rest)? {
+                [0] => Ok(HelloInstruction::CheckUpgradeAuthority(false)),
+                [1] => Ok(HelloInstruction::CheckUpgradeAuthority(true)),
+                _ => Err(HelloError::InvalidInstruction.into()),
+            },
+            21 => Ok(HelloInstruction::ProposeSet(u32::from_le_bytes(fixed(rest)?))),
+            22 => complete(rest, HelloInstruction::ConfirmSet),
+            23 => Ok(HelloInstruction::SetAllowlist(fixed(rest)?)),
+            24 => {
+                // [count: u8] followed by count 32 byte hashes, nothing after them
+                let (&count, hashes) = rest.split_first().ok_or(HelloError::InvalidInstruction)?;
+                if count as usize > MAX_PROOF_LEN {
+                    return Err(HelloError::InvalidInstruction.into());
+                }
+                check_len(hashes, count as usize * 32)?;
+                let mut proof = Vec::with_capacity(count as usize);
+                for hash in hashes.chunks(32) {
+                    proof.push(hash.try_into().map_err(|_| HelloError::InvalidInstruction)?);
+                }
+                Ok(HelloInstruction::AllowlistedIncrement(proof))
+            },
+            25 => complete(rest, HelloInstruction::SignedIncrement),
+            26 => complete(rest, HelloInstruction::GetMultiple),
+            27 => complete(rest, HelloInstruction::CancelSet),
+            _ => Err(HelloError::InvalidInstruction.into())
+        }
+    }
+
+    // the opposite of unpack(), used by the builders below and by Batch to
+    // re-encode each operation for its CPI. A length over the limit unpack()
+    // checks panics, rather than being truncated into its u8 prefix and
+    // encoding something else.
+    pub fn pack(&self) -> Vec<u8> {
+        match self {
+            HelloInstruction::Increment => vec![0],
+            HelloInstruction::Decrement => vec![1],
+            HelloInstruction::Set(value) => {
+                let mut buf = vec![2];
+                buf.extend_from_slice(&value.to_le_bytes());
+                buf
+            }
+            HelloInstruction::GetVersion => vec![3],
+            HelloInstruction::Batch(ops) => {
+                assert!(
+                    ops.len() <= MAX_BATCH_LEN,
+                    "a Batch carries at most {} ops",
+                    MAX_BATCH_LEN
+                );
+                let mut buf = vec![4, ops.len() as u8];
+                for op in ops {
+                    let op = op.pack();
+                    assert!(op.len() <= u8::MAX as usize, "a Batch op is at most 255 bytes");
+                    buf.push(op.len() as u8);
+                    buf.extend_from_slice(&op);
+                }
+                buf
+            }
+            HelloInstruction::MirrorIncrement => vec![5],
+            HelloInstruction::CopyFrom => vec![6],
+            HelloInstruction::Upvote => vec![7],
+            HelloInstruction::Downvote => vec![8],
+            HelloInstruction::CreatePoll(labels) => {
+                assert!(
+                    !labels.is_empty() && labels.len() <= MAX_POLL_OPTIONS,
+                    "a poll has 1 to {} options",
+                    MAX_POLL_OPTIONS
+                );
+                let mut buf = vec![9, labels.len() as u8];
+                for label in labels {
+                    assert!(
+                        label.len() <= MAX_POLL_LABEL_LEN,
+                        "a poll label is at most {} bytes",
+                        MAX_POLL_LABEL_LEN
+                    );
+                    buf.push(label.len() as u8);
+                    buf.extend_from_slice(label.as_bytes());
+                }
+                buf
+            }
+            HelloInstruction::Vote(index) => vec![10, *index],
+            HelloInstruction::ClosePoll => vec![11],
+            HelloInstruction::Attest(hash) => {
+                let mut buf = vec![12];
+                buf.extend_from_slice(hash);
+                buf
+            }
+            HelloInstruction::SetUri(uri) => {
+                assert!(
+                    uri.len() <= MAX_URI_LEN,
+                    "a metadata URI is at most {} bytes",
+                    MAX_URI_LEN
+                );
+                let mut buf = vec![13, uri.len() as u8];
+                buf.extend_from_slice(uri.as_bytes());
+                buf
+            }
+            HelloInstruction::SetLanguage(language) => vec![14, language[0], language[1]],
+            HelloInstruction::Idempotent(op_id, op) => {
+                let mut buf = vec![15];
+                buf.extend_from_slice(op_id);
+                buf.extend_from_slice(&op.pack());
+                buf
+            }
+            HelloInstruction::Deposit(amount, target) => {
+                let mut buf = vec![16];
+                buf.extend_from_slice(&amount.to_le_bytes());
+                buf.extend_from_slice(&target.to_le_bytes());
+                buf
+            }
+            HelloInstruction::Withdraw => vec![17],
+            HelloInstruction::ClaimBadge(milestone) => {
+                let mut buf = vec![18];
+                buf.extend_from_slice(&milestone.to_le_bytes());
+                buf
+            }
+            HelloInstruction::CheckIn => vec![19],
+            HelloInstruction::CheckUpgradeAuthority(strict) => vec![20, *strict as u8],
+            HelloInstruction::ProposeSet(value) => {
+                let mut buf = vec![21];
+                buf.extend_from_slice(&value.to_le_bytes());
+                buf
+            }
+            HelloInstruction::ConfirmSet => vec![22],
+            HelloInstruction::SetAllowlist(root) => {
+                let mut buf = vec![23];
+                buf.extend_from_slice(root);
+                buf
+            }
+            HelloInstruction::AllowlistedIncrement(proof) => {
+                assert!(
+                    proof.len() <= MAX_PROOF_LEN,
+                    "a proof is at most {} hashes",
+                    MAX_PROOF_LEN
+                );
+                let mut buf = vec![24, proof.len() as u8];
+                for hash in proof {
+                    buf.extend_from_slice(hash);
+                }
+                buf
+            }
+            HelloInstruction::SignedIncrement => vec![25],
+            HelloInstruction::GetMultiple => vec![26],
+            HelloInstruction::CancelSet => vec![27],
+        }
+    }
+
+    // Batch entries are run one CPI deep, so they may not be Batches
+    // themselves. Keeping nesting to a single level means a Batch only ever
+    // adds one frame to the invoke stack, well under the runtime's depth limit
+    // of 4, wherever in a CPI chain it is called from.
+    fn is_batchable(&self) -> bool {
+        matches!(
+            self,
+            HelloInstruction::Increment | HelloInstruction::Decrement | HelloInstruction::Set(_)
+        )
+    }
+
+    /// Whether the instruction adds to a greeting counter. A build with the
+    /// `require-memo` feature only runs these in a transaction with an SPL
+    /// Memo, and they need with_instructions_sysvar() for the check.
+    pub fn increments(&self) -> bool {
+        match self {
+            HelloInstruction::Increment
+            | HelloInstruction::MirrorIncrement
+            | HelloInstruction::Upvote
+            | HelloInstruction::CheckIn
+            | HelloInstruction::AllowlistedIncrement(_)
+            | HelloInstruction::SignedIncrement => true,
+            HelloInstruction::Batch(ops) => ops.iter().any(HelloInstruction::increments),
+            HelloInstruction::Idempotent(_, op) => op.increments(),
+            _ => false,
+        }
+    }
+}
+
+// `data` is exactly `len` bytes: InvalidInstruction if it is shorter,
+// TrailingInstructionData if it is longer
+fn check_len(data: &[u8], len: usize) -> Result<(), ProgramError> {
+    match data.len().cmp(&len) {
+        Ordering::Less => Err(HelloError::InvalidInstruction.into()),
+        Ordering::Equal => Ok(()),
+        Ordering::Greater => Err(HelloError::TrailingInstructionData.into()),
+    }
+}
+
+// The N bytes of a fixed size argument that ends the data
+fn fixed<const N: usize>(rest: &[u8]) -> Result<[u8; N], ProgramError> {
+    check_len(rest, N)?;
+    rest.try_into().map_err(|_| HelloError::InvalidInstruction.into())
+}
+
+// `instruction`, if nothing of the data is left after it
+fn complete(
+    rest: &[u8],
+    instruction: HelloInstruction,
+) -> Result<HelloInstruction, ProgramError> {
+    check_len(rest, 0)?;
+    Ok(instruction)
+}
+
+// Instruction builders, so clients and other programs don't have to know the
+// byte layout decoded by unpack()
+
+/// Increment the counter in `greeting`
+pub fn increment(program_id: &Pubkey, greeting: &Pubkey) -> Instruction {
+    greeting_instruction(program_id, greeting, HelloInstruction::Increment)
+}
+
+/// Decrement the counter in `greeting`
+pub fn decrement(program_id: &Pubkey, greeting: &Pubkey) -> Instruction {
+    greeting_instruction(program_id, greeting, HelloInstruction::Decrement)
+}
+
+/// Set the counter in `greeting` to `value`. Refused once the counter is
+/// above SET_CONFIRM_THRESHOLD, see propose_set()
+pub fn set(program_id: &Pubkey, greeting: &Pubkey, value: u32) -> Instruction {
+    greeting_instruction(program_id, greeting, HelloInstruction::Set(value))
+}
+
+/// Read the program version, the result is returned as return data
+pub fn get_version(program_id: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![],
+        data: HelloInstruction::GetVersion.pack(),
+    }
+}
+
+/// Run `ops` against `greeting` in one instruction. The program account is
+/// passed as well because each op is a CPI back into the program. Panics
+/// with more than MAX_BATCH_LEN ops
+pub fn batch(program_id: &Pubkey, greeting: &Pubkey, ops: Vec<HelloInstruction>) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*greeting, false),
+            AccountMeta::new_readonly(*program_id, false),
+        ],
+        data: HelloInstruction::Batch(ops).pack(),
+    }
+}
+
+/// Increment `greeting` and then `mirror_greeting`, which belongs to the
+/// mirror deployment `config::mirror::id()`
+pub fn mirror_increment(
+    program_id: &Pubkey,
+    greeting: &Pubkey,
+    mirror_greeting: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*greeting, false),
+            AccountMeta::new(*mirror_greeting, false),
+            AccountMeta::new_readonly(crate::config::mirror::id(), false),
+        ],
+        data: HelloInstruction::MirrorIncrement.pack(),
+    }
+}
+
+/// Copy the counter of `source`, an account owned by `config::foreign::id()`,
+/// into `greeting`
+pub fn copy_from(program_id: &Pubkey, greeting: &Pubkey, source: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*greeting, false),
+            AccountMeta::new_readonly(*source, false),
+        ],
+        data: HelloInstruction::CopyFrom.pack(),
+    }
+}
+
+/// Upvote `greeting` as `voter`, who signs and pays for the receipt
+pub fn upvote(program_id: &Pubkey, greeting: &Pubkey, voter: &Pubkey) -> Instruction {
+    vote_instruction(program_id, greeting, voter, HelloInstruction::Upvote)
+}
+
+/// Downvote `greeting` as `voter`, who signs and pays for the receipt
+pub fn downvote(program_id: &Pubkey, greeting: &Pubkey, voter: &Pubkey) -> Instruction {
+    vote_instruction(program_id, greeting, voter, HelloInstruction::Downvote)
+}
+
+/// Set up `poll`, an account of Poll::LEN zeroed bytes owned by the
+/// program, with one option per label. `authority` signs and may close it.
+/// Panics unless there are 1 to MAX_POLL_OPTIONS labels of at most
+/// MAX_POLL_LABEL_LEN bytes
+pub fn create_poll(
+    program_id: &Pubkey,
+    poll: &Pubkey,
+    authority: &Pubkey,
+    labels: Vec<String>,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*poll, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: HelloInstruction::CreatePoll(labels).pack(),
+    }
+}
+
+/// Vote for option `index` of `poll` as `voter`, who signs and pays for
+/// the receipt
+pub fn vote(program_id: &Pubkey, poll: &Pubkey, voter: &Pubkey, index: u8) -> Instruction {
+    vote_instruction(program_id, poll, voter, HelloInstruction::Vote(index))
+}
+
+/// Stop voting on `poll`, signed by its authority
+pub fn close_poll(program_id: &Pubkey, poll: &Pubkey, authority: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*poll, false),
+            AccountMeta::new_readonly(*authority, true),
+        ],
+        data: HelloInstruction::ClosePoll.pack(),
+    }
+}
+
+/// Record `hash` on `greeting` as `attester`, who signs and pays for the
+/// attestation account if it doesn't exist yet
+pub fn attest(
+    program_id: &Pubkey,
+    greeting: &Pubkey,
+    attester: &Pubkey,
+    hash: [u8; 32],
+) -> Instruction {
+    let (attestation, _) = AttestationSeeds::new(greeting).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*greeting, false),
+            AccountMeta::new(*attester, true),
+            AccountMeta::new(attestation, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: HelloInstruction::Attest(hash).pack(),
+    }
+}
+
+/// Point the metadata of `greeting` at `uri`, signed by `authority`. The
+/// first call creates the record, paid for by `authority`. Panics if `uri`
+/// is over MAX_URI_LEN bytes
+pub fn set_uri(
+    program_id: &Pubkey,
+    greeting: &Pubkey,
+    authority: &Pubkey,
+    uri: String,
+) -> Instruction {
+    let (metadata, _) = MetadataSeeds::new(greeting).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*greeting, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(metadata, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: HelloInstruction::SetUri(uri).pack(),
+    }
+}
+
+/// Set the language `greeting` is logged in, signed by its metadata
+/// `authority`. The first call creates the record, paid for by `authority`
+pub fn set_language(
+    program_id: &Pubkey,
+    greeting: &Pubkey,
+    authority: &Pubkey,
+    language: [u8; 2],
+) -> Instruction {
+    let (metadata, _) = MetadataSeeds::new(greeting).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*greeting, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(metadata, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: HelloInstruction::SetLanguage(language).pack(),
+    }
+}
+
+/// Pass the metadata record of `greeting` to an increment(), decrement()
+/// or set() instruction so it logs in the greeting's language
+pub fn with_language(mut instruction: Instruction, greeting: &Pubkey) -> Instruction {
+    let (metadata, _) = MetadataSeeds::new(greeting).pda(&instruction.program_id);
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(metadata, false));
+    instruction
+}
+
+/// Pass the instructions sysvar to an instruction that increments a
+/// counter (see HelloInstruction::increments()), which a build with the
+/// `require-memo` feature needs to find the memo. Add it after
+/// with_language() if both are used.
+pub fn with_instructions_sysvar(mut instruction: Instruction) -> Instruction {
+    instruction
+        .accounts
+        .push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    instruction
+}
+
+fn vote_instruction(
+    program_id: &Pubkey,
+    target: &Pubkey,
+    voter: &Pubkey,
+    instruction: HelloInstruction,
+) -> Instruction {
+    let (receipt, _) = ReceiptSeeds::new(target, voter).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*target, false),
+            AccountMeta::new(*voter, true),
+            AccountMeta::new(receipt, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction.pack(),
+    }
+}
+
+/// Run `op`, an Increment, Decrement or Set, on `greeting` unless `op_id`
+/// was used for one of its recent operations. `payer` signs and pays for
+/// the window of recent op_ids the first time
+pub fn idempotent(
+    program_id: &Pubkey,
+    greeting: &Pubkey,
+    payer: &Pubkey,
+    op_id: [u8; 16],
+    op: HelloInstruction,
+) -> Instruction {
+    let (recent_ops, _) = RecentOpsSeeds::new(greeting).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*greeting, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(recent_ops, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: HelloInstruction::Idempotent(op_id, Box::new(op)).pack(),
+    }
+}
+
+/// Move `amount` lamports from `authority`, who signs, into its vault for
+/// `greeting`. The first deposit opens the vault, paid for by `authority`,
+/// with `target` as the counter value that unlocks it. Set can reach the
+/// target in the same transaction as the Withdraw, see state::Vault
+pub fn deposit(
+    program_id: &Pubkey,
+    greeting: &Pubkey,
+    authority: &Pubkey,
+    amount: u64,
+    target: u32,
+) -> Instruction {
+    let (vault, _) = VaultSeeds::new(greeting, authority).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*greeting, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: HelloInstruction::Deposit(amount, target).pack(),
+    }
+}
+
+/// Close the vault of `authority` for `greeting` and return its lamports to
+/// `authority`, who signs. Fails until the counter reaches the target
+pub fn withdraw(program_id: &Pubkey, greeting: &Pubkey, authority: &Pubkey) -> Instruction {
+    let (vault, _) = VaultSeeds::new(greeting, authority).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*greeting, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(vault, false),
+        ],
+        data: HelloInstruction::Withdraw.pack(),
+    }
+}
+
+/// Create the badge of `greeting` for `milestone`, paid for by `payer`,
+/// who signs
+pub fn claim_badge(
+    program_id: &Pubkey,
+    greeting: &Pubkey,
+    payer: &Pubkey,
+    milestone: u32,
+) -> Instruction {
+    let (badge, _) = BadgeSeeds::new(greeting, milestone).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*greeting, false),
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(badge, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: HelloInstruction::ClaimBadge(milestone).pack(),
+    }
+}
+
+/// Increment `greeting` and record a check-in for `user`, who signs and
+/// pays for their streak account the first time
+pub fn check_in(program_id: &Pubkey, greeting: &Pubkey, user: &Pubkey) -> Instruction {
+    let (streak, _) = StreakSeeds::new(greeting, user).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*greeting, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new(streak, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: HelloInstruction::CheckIn.pack(),
+    }
+}
+
+/// Log the upgrade authority of `program_id`, failing if `strict` and it
+/// isn't config::upgrade_authority. The program has to be deployed with the
+/// upgradeable loader, see seeds::program_data_address().
+pub fn check_upgrade_authority(program_id: &Pubkey, strict: bool) -> Instruction {
+    let program_data = seeds::program_data_address(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new_readonly(program_data, false)],
+        data: HelloInstruction::CheckUpgradeAuthority(strict).pack(),
+    }
+}
+
+/// Propose setting the counter of `greeting` to `value`, signed by
+/// `proposer`, who pays for the pending account
+pub fn propose_set(
+    program_id: &Pubkey,
+    greeting: &Pubkey,
+    proposer: &Pubkey,
+    value: u32,
+) -> Instruction {
+    let (pending, _) = PendingSetSeeds::new(greeting).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*greeting, false),
+            AccountMeta::new(*proposer, true),
+            AccountMeta::new(pending, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: HelloInstruction::ProposeSet(value).pack(),
+    }
+}
+
+/// Apply the pending Set of `greeting` and refund its account to
+/// `proposer`, the signer of the ProposeSet
+pub fn confirm_set(program_id: &Pubkey, greeting: &Pubkey, proposer: &Pubkey) -> Instruction {
+    let (pending, _) = PendingSetSeeds::new(greeting).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*greeting, false),
+            AccountMeta::new(*proposer, false),
+            AccountMeta::new(pending, false),
+        ],
+        data: HelloInstruction::ConfirmSet.pack(),
+    }
+}
+
+/// Drop the pending Set of `greeting` without applying it, signed by
+/// `proposer`, who gets its rent back
+pub fn cancel_set(program_id: &Pubkey, greeting: &Pubkey, proposer: &Pubkey) -> Instruction {
+    let (pending, _) = PendingSetSeeds::new(greeting).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*greeting, false),
+            AccountMeta::new(*proposer, true),
+            AccountMeta::new(pending, false),
+        ],
+        data: HelloInstruction::CancelSet.pack(),
+    }
+}
+
+/// Set the allowlist root of `greeting`, signed by `authority`. The first
+/// call creates the allowlist, paid for by `authority`
+pub fn set_allowlist(
+    program_id: &Pubkey,
+    greeting: &Pubkey,
+    authority: &Pubkey,
+    root: [u8; 32],
+) -> Instruction {
+    let (allowlist, _) = AllowlistSeeds::new(greeting).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*greeting, false),
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(allowlist, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: HelloInstruction::SetAllowlist(root).pack(),
+    }
+}
+
+/// Increment `greeting` as `member`, who signs, with `proof` from
+/// merkle::proof() showing they are in its allowlist. Panics if `proof` is
+/// over MAX_PROOF_LEN hashes
+pub fn allowlisted_increment(
+    program_id: &Pubkey,
+    greeting: &Pubkey,
+    member: &Pubkey,
+    proof: Vec<[u8; 32]>,
+) -> Instruction {
+    let (allowlist, _) = AllowlistSeeds::new(greeting).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*greeting, false),
+            AccountMeta::new_readonly(*member, true),
+            AccountMeta::new_readonly(allowlist, false),
+        ],
+        data: HelloInstruction::AllowlistedIncrement(proof).pack(),
+    }
+}
+
+/// Increment `greeting` as `greeter`, who signs and pays for the greeting's
+/// GreeterStats the first time
+pub fn signed_increment(program_id: &Pubkey, greeting: &Pubkey, greeter: &Pubkey) -> Instruction {
+    let (stats, _) = GreeterStatsSeeds::new(greeting).pda(program_id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*greeting, false),
+            AccountMeta::new(*greeter, true),
+            AccountMeta::new(stats, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: HelloInstruction::SignedIncrement.pack(),
+    }
+}
+
+/// Read the counters of `greetings`, at most MAX_GET_MULTIPLE_LEN of them.
+/// They are returned in the same order as return data, decode it with
+/// `Vec::<u32>::try_from_slice`.
+pub fn get_multiple(program_id: &Pubkey, greetings: &[Pubkey]) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: greetings
+            .iter()
+            .map(|greeting| AccountMeta::new_readonly(*greeting, false))
+            .collect(),
+        data: HelloInstruction::GetMultiple.pack(),
+    }
+}
+
+fn greeting_instruction(
+    program_id: &Pubkey,
+    greeting: &Pubkey,
+    instruction: HelloInstruction,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![AccountMeta::new(*greeting, false)],
+        data: instruction.pack(),
+    }
+}
\ No newline at end of file
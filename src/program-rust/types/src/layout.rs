@@ -0,0 +1,205 @@
+//src/program-rust/types/src/layout.rs
+// Fixed offset encoding of the hot account types
+//
+// Borsh decodes a struct field by field through a cursor, checking the
+// remaining length before each one. GreetingAccount, Streak and
+// GreeterStats only have fixed size fields, so their Borsh encoding is a
+// fixed layout and can be read and written at known offsets with a single
+// length check instead. The bytes are the same either way,
+// test_fixed_layout_matches_borsh compares every type with its derived
+// encoding.
+//
+// With the `fixed-layout` feature the Borsh derives of these types are
+// replaced with impls that go through FixedLayout, so the program keeps
+// calling try_from_slice() and serialize() and only the code behind them
+// changes.
+
+use crate::state::{GreeterStats, GreetingAccount, Streak, BLOOM_BYTES, HLL_REGISTERS};
+use solana_program::pubkey::Pubkey;
+
+/// An account type encoded at fixed offsets, in the same bytes as Borsh
+pub trait FixedLayout: Sized {
+    /// Size in bytes of the encoding
+    const SIZE: usize;
+
+    /// Decode from `data`, which must be exactly SIZE bytes long
+    fn read(data: &[u8]) -> Self;
+
+    /// Decode from `data` over `self`, same as read(). Types too large to
+    /// pass through the stack decode field by field instead.
+    fn read_into(&mut self, data: &[u8]) {
+        *self = Self::read(data);
+    }
+
+    /// Encode into `data`, which must be exactly SIZE bytes long
+    fn write(&self, data: &mut [u8]);
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_le_bytes(bytes)
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&data[offset..offset + 8]);
+    u64::from_le_bytes(bytes)
+}
+
+fn read_pubkey(data: &[u8], offset: usize) -> Pubkey {
+    let mut bytes = [0; 32];
+    bytes.copy_from_slice(&data[offset..offset + 32]);
+    Pubkey::new_from_array(bytes)
+}
+
+impl FixedLayout for GreetingAccount {
+    const SIZE: usize = GreetingAccount::LEN;
+
+    fn read(data: &[u8]) -> Self {
+        Self {
+            counter: read_u32(data, GreetingAccount::COUNTER_OFFSET),
+        }
+    }
+
+    fn write(&self, data: &mut [u8]) {
+        data[..4].copy_from_slice(&self.counter.to_le_bytes());
+    }
+}
+
+impl FixedLayout for Streak {
+    const SIZE: usize = Streak::LEN;
+
+    fn read(data: &[u8]) -> Self {
+        Self {
+            greeting: read_pubkey(data, 0),
+            user: read_pubkey(data, 32),
+            last_epoch: read_u64(data, 64),
+            current_streak: read_u32(data, 72),
+            best_streak: read_u32(data, 76),
+        }
+    }
+
+    fn write(&self, data: &mut [u8]) {
+        data[..32].copy_from_slice(self.greeting.as_ref());
+        data[32..64].copy_from_slice(self.user.as_ref());
+        data[64..72].copy_from_slice(&self.last_epoch.to_le_bytes());
+        data[72..76].copy_from_slice(&self.current_streak.to_le_bytes());
+        data[76..80].copy_from_slice(&self.best_streak.to_le_bytes());
+    }
+}
+
+// offsets of the GreeterStats fields after the bloom filter
+const HLL_OFFSET: usize = 36 + BLOOM_BYTES;
+const HLL_ESTIMATE_OFFSET: usize = HLL_OFFSET + HLL_REGISTERS;
+
+impl FixedLayout for GreeterStats {
+    const SIZE: usize = GreeterStats::LEN;
+
+    fn read(data: &[u8]) -> Self {
+        let mut stats = Self::new(Pubkey::default());
+        stats.read_into(data);
+        stats
+    }
+
+    fn read_into(&mut self, data: &[u8]) {
+        self.greeting = read_pubkey(data, 0);
+        self.unique_greeters_estimate = read_u32(data, 32);
+        self.bloom.copy_from_slice(&data[36..HLL_OFFSET]);
+        self.hll
+            .copy_from_slice(&data[HLL_OFFSET..HLL_ESTIMATE_OFFSET]);
+        self.hll_estimate = read_u64(data, HLL_ESTIMATE_OFFSET);
+    }
+
+    fn write(&self, data: &mut [u8]) {
+        data[..32].copy_from_slice(self.greeting.as_ref());
+        data[32..36].copy_from_slice(&self.unique_greeters_estimate.to_le_bytes());
+        data[36..HLL_OFFSET].copy_from_slice(&self.bloom);
+        data[HLL_OFFSET..HLL_ESTIMATE_OFFSET].copy_from_slice(&self.hll);
+        data[HLL_ESTIMATE_OFFSET..].copy_from_slice(&self.hll_estimate.to_le_bytes());
+    }
+}
+
+// Borsh impls over FixedLayout, in place of the derives. Decoding takes
+// SIZE bytes off the front of the buffer like the derived code does, so
+// try_from_slice() still rejects data with bytes left over.
+#[cfg(feature = "fixed-layout")]
+macro_rules! borsh_via_fixed_layout {
+    ($($t:ty),+) => {
+        $(
+            impl borsh::BorshSerialize for $t {
+                fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                    let mut bytes = [0; <$t as FixedLayout>::SIZE];
+                    self.write(&mut bytes);
+                    writer.write_all(&bytes)
+                }
+            }
+
+            impl borsh::BorshDeserialize for $t {
+                fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+                    if buf.len() < <$t as FixedLayout>::SIZE {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidInput,
+                            "Unexpected length of input",
+                        ));
+                    }
+                    let (data, rest) = buf.split_at(<$t as FixedLayout>::SIZE);
+                    let value = Self::read(data);
+                    *buf = rest;
+                    Ok(value)
+                }
+            }
+        )+
+    };
+}
+
+#[cfg(feature = "fixed-layout")]
+borsh_via_fixed_layout!(GreetingAccount, Streak, GreeterStats);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use borsh::{BorshDeserialize, BorshSerialize};
+
+    // Encode `value` both ways and decode the bytes both ways
+    fn check<T: FixedLayout + BorshSerialize + BorshDeserialize>(value: &T) -> T {
+        let borsh = value.try_to_vec().unwrap();
+        assert_eq!(borsh.len(), T::SIZE);
+        let mut fixed = vec![0; T::SIZE];
+        value.write(&mut fixed);
+        assert_eq!(fixed, borsh);
+
+        let decoded = T::try_from_slice(&fixed).unwrap();
+        assert_eq!(decoded.try_to_vec().unwrap(), borsh);
+        // a byte short or a byte over is an error, as with the derives
+        assert!(T::try_from_slice(&fixed[..T::SIZE - 1]).is_err());
+        fixed.push(0);
+        assert!(T::try_from_slice(&fixed).is_err());
+        T::read(&borsh)
+    }
+
+    #[test]
+    fn test_fixed_layout_matches_borsh() {
+        let greeting = check(&GreetingAccount {
+            counter: 0x0102_0304,
+        });
+        assert_eq!(greeting.counter, 0x0102_0304);
+
+        let streak = Streak {
+            greeting: Pubkey::new_unique(),
+            user: Pubkey::new_unique(),
+            last_epoch: u64::MAX - 1,
+            current_streak: 3,
+            best_streak: 9,
+        };
+        assert_eq!(check(&streak), streak);
+
+        let mut stats = GreeterStats::new(Pubkey::new_unique());
+        for _ in 0..50 {
+            stats.record(&Pubkey::new_unique());
+        }
+        stats.bloom[BLOOM_BYTES - 1] = 0xff;
+        stats.hll[0] = 7;
+        assert_eq!(check(&stats), stats);
+    }
+}
@@ -0,0 +1,15 @@
+// The hello world program's interface: what goes into an instruction, what
+// its accounts hold and the errors it can fail with. The program crate and
+// clients both build on this, clients without pulling in the entrypoint.
+
+pub mod config;
+pub mod discriminator;
+pub mod error;
+pub mod instruction;
+pub mod layout;
+pub mod merkle;
+pub mod metrics;
+pub mod seeds;
+pub mod state;
+
+pub use crate::state::GreetingAccount;
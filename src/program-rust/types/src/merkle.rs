@@ -0,0 +1,108 @@
+//src/program-rust/types/src/merkle.rs
+// Merkle tree over allowlisted wallets, checked by AllowlistedIncrement
+//
+// Leaves and inner nodes are sha256 hashes with different prefixes, so an
+// inner node can never be passed off as a leaf. The two children of a node
+// are hashed smaller one first, which means a proof is only the sibling
+// hashes from the leaf up, with no left/right flags.
+//
+// The program only needs leaf() and verify(). root() and proof() are for
+// whoever publishes the allowlist, they build the whole tree in memory.
+
+use solana_program::{hash::hashv, pubkey::Pubkey};
+
+/// Longest proof AllowlistedIncrement accepts, enough for 2^20 members
+pub const MAX_PROOF_LEN: usize = 20;
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// The leaf of `member`
+pub fn leaf(member: &Pubkey) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, member.as_ref()]).to_bytes()
+}
+
+fn parent(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (first, second) = if a <= b { (a, b) } else { (b, a) };
+    hashv(&[NODE_PREFIX, first, second]).to_bytes()
+}
+
+/// Whether `proof` leads from `leaf` to `root`
+pub fn verify(root: &[u8; 32], leaf: [u8; 32], proof: &[[u8; 32]]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |node, sibling| parent(&node, sibling));
+    computed == *root
+}
+
+// Every level of the tree, leaves first. A node without a sibling is
+// carried up to the next level unchanged.
+fn levels(members: &[Pubkey]) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![members.iter().map(leaf).collect::<Vec<_>>()];
+    while levels[levels.len() - 1].len() > 1 {
+        let next = levels[levels.len() - 1]
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => parent(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// Root of the tree over `members`, all zero if there are none
+pub fn root(members: &[Pubkey]) -> [u8; 32] {
+    levels(members)
+        .last()
+        .and_then(|level| level.first().copied())
+        .unwrap_or([0; 32])
+}
+
+/// Proof for `members[index]`, None if it is out of range
+pub fn proof(members: &[Pubkey], index: usize) -> Option<Vec<[u8; 32]>> {
+    if index >= members.len() {
+        return None;
+    }
+    let levels = levels(members);
+    let mut proof = vec![];
+    let mut index = index;
+    for level in &levels[..levels.len() - 1] {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        index /= 2;
+    }
+    Some(proof)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_proofs_verify() {
+        // odd sizes leave nodes without a sibling on some levels
+        for size in 1..=9 {
+            let members: Vec<Pubkey> = (0..size).map(|_| Pubkey::new_unique()).collect();
+            let root = root(&members);
+            for (index, member) in members.iter().enumerate() {
+                let proof = proof(&members, index).unwrap();
+                assert!(proof.len() <= MAX_PROOF_LEN);
+                assert!(verify(&root, leaf(member), &proof));
+                assert!(!verify(&root, leaf(&Pubkey::new_unique()), &proof));
+            }
+            assert_eq!(proof(&members, size), None);
+        }
+
+        let members: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let root = root(&members);
+        let mut tampered = proof(&members, 0).unwrap();
+        tampered[1][0] ^= 1;
+        assert!(!verify(&root, leaf(&members[0]), &tampered));
+        // stopping short of the root doesn't verify either
+        assert!(!verify(&root, leaf(&members[0]), &tampered[..1]));
+    }
+}
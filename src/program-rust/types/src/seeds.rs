@@ -0,0 +1,359 @@
+//src/program-rust/types/src/seeds.rs
+// Seeds of every program derived address, in one place
+//
+// Each PDA kind has a struct holding the keys it is derived from. pda()
+// finds the address, with_bump() gives the seeds to sign for it with. The
+// account contexts, the processor and the client side instruction builders
+// all go through these, so the seed order can't drift between them.
+
+use solana_program::{bpf_loader_upgradeable, pubkey::Pubkey};
+
+/// Seed prefix of VoteReceipt accounts
+pub const RECEIPT_SEED: &[u8] = b"receipt";
+
+/// Seed prefix of Attestation accounts
+pub const ATTESTATION_SEED: &[u8] = b"attestation";
+
+/// Seed prefix of GreetingMetadata accounts
+pub const METADATA_SEED: &[u8] = b"metadata";
+
+/// Seed prefix of RecentOps accounts
+pub const RECENT_OPS_SEED: &[u8] = b"recent_ops";
+
+/// Seed prefix of Vault accounts
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// Seed prefix of Badge accounts
+pub const BADGE_SEED: &[u8] = b"badge";
+
+/// Seed prefix of Streak accounts
+pub const STREAK_SEED: &[u8] = b"streak";
+
+/// Seed prefix of PendingSet accounts
+pub const PENDING_SET_SEED: &[u8] = b"pending_set";
+
+/// Seed prefix of Allowlist accounts
+pub const ALLOWLIST_SEED: &[u8] = b"allowlist";
+
+/// Seed prefix of GreeterStats accounts
+pub const GREETER_STATS_SEED: &[u8] = b"greeter_stats";
+
+/// The receipt recording that `voter` voted on `target`
+pub struct ReceiptSeeds<'a> {
+    pub target: &'a Pubkey,
+    pub voter: &'a Pubkey,
+}
+
+impl<'a> ReceiptSeeds<'a> {
+    pub fn new(target: &'a Pubkey, voter: &'a Pubkey) -> Self {
+        Self { target, voter }
+    }
+
+    pub fn pda(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[RECEIPT_SEED, self.target.as_ref(), self.voter.as_ref()],
+            program_id,
+        )
+    }
+
+    pub fn with_bump(&self, bump: &'a [u8; 1]) -> [&'a [u8]; 4] {
+        [RECEIPT_SEED, self.target.as_ref(), self.voter.as_ref(), bump]
+    }
+}
+
+/// The attestation record of `greeting`
+pub struct AttestationSeeds<'a> {
+    pub greeting: &'a Pubkey,
+}
+
+impl<'a> AttestationSeeds<'a> {
+    pub fn new(greeting: &'a Pubkey) -> Self {
+        Self { greeting }
+    }
+
+    pub fn pda(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[ATTESTATION_SEED, self.greeting.as_ref()], program_id)
+    }
+
+    pub fn with_bump(&self, bump: &'a [u8; 1]) -> [&'a [u8]; 3] {
+        [ATTESTATION_SEED, self.greeting.as_ref(), bump]
+    }
+}
+
+/// The metadata record of `greeting`
+pub struct MetadataSeeds<'a> {
+    pub greeting: &'a Pubkey,
+}
+
+impl<'a> MetadataSeeds<'a> {
+    pub fn new(greeting: &'a Pubkey) -> Self {
+        Self { greeting }
+    }
+
+    pub fn pda(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[METADATA_SEED, self.greeting.as_ref()], program_id)
+    }
+
+    pub fn with_bump(&self, bump: &'a [u8; 1]) -> [&'a [u8]; 3] {
+        [METADATA_SEED, self.greeting.as_ref(), bump]
+    }
+}
+
+/// The window of recent op_ids of `greeting`
+pub struct RecentOpsSeeds<'a> {
+    pub greeting: &'a Pubkey,
+}
+
+impl<'a> RecentOpsSeeds<'a> {
+    pub fn new(greeting: &'a Pubkey) -> Self {
+        Self { greeting }
+    }
+
+    pub fn pda(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[RECENT_OPS_SEED, self.greeting.as_ref()], program_id)
+    }
+
+    pub fn with_bump(&self, bump: &'a [u8; 1]) -> [&'a [u8]; 3] {
+        [RECENT_OPS_SEED, self.greeting.as_ref(), bump]
+    }
+}
+
+/// The vault `authority` funds against the counter of `greeting`
+pub struct VaultSeeds<'a> {
+    pub greeting: &'a Pubkey,
+    pub authority: &'a Pubkey,
+}
+
+impl<'a> VaultSeeds<'a> {
+    pub fn new(greeting: &'a Pubkey, authority: &'a Pubkey) -> Self {
+        Self {
+            greeting,
+            authority,
+        }
+    }
+
+    pub fn pda(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[VAULT_SEED, self.greeting.as_ref(), self.authority.as_ref()],
+            program_id,
+        )
+    }
+
+    pub fn with_bump(&self, bump: &'a [u8; 1]) -> [&'a [u8]; 4] {
+        [
+            VAULT_SEED,
+            self.greeting.as_ref(),
+            self.authority.as_ref(),
+            bump,
+        ]
+    }
+}
+
+/// The badge of `greeting` for reaching `milestone`. The milestone is held
+/// as its little endian bytes, which is how it goes into the seeds.
+pub struct BadgeSeeds<'a> {
+    pub greeting: &'a Pubkey,
+    pub milestone: [u8; 4],
+}
+
+impl<'a> BadgeSeeds<'a> {
+    pub fn new(greeting: &'a Pubkey, milestone: u32) -> Self {
+        Self {
+            greeting,
+            milestone: milestone.to_le_bytes(),
+        }
+    }
+
+    pub fn pda(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[BADGE_SEED, self.greeting.as_ref(), &self.milestone],
+            program_id,
+        )
+    }
+
+    pub fn with_bump(&'a self, bump: &'a [u8; 1]) -> [&'a [u8]; 4] {
+        [BADGE_SEED, self.greeting.as_ref(), &self.milestone, bump]
+    }
+}
+
+/// The check-in streak of `user` on `greeting`
+pub struct StreakSeeds<'a> {
+    pub greeting: &'a Pubkey,
+    pub user: &'a Pubkey,
+}
+
+impl<'a> StreakSeeds<'a> {
+    pub fn new(greeting: &'a Pubkey, user: &'a Pubkey) -> Self {
+        Self { greeting, user }
+    }
+
+    pub fn pda(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[STREAK_SEED, self.greeting.as_ref(), self.user.as_ref()],
+            program_id,
+        )
+    }
+
+    pub fn with_bump(&self, bump: &'a [u8; 1]) -> [&'a [u8]; 4] {
+        [STREAK_SEED, self.greeting.as_ref(), self.user.as_ref(), bump]
+    }
+}
+
+/// The proposed Set of `greeting` waiting for ConfirmSet
+pub struct PendingSetSeeds<'a> {
+    pub greeting: &'a Pubkey,
+}
+
+impl<'a> PendingSetSeeds<'a> {
+    pub fn new(greeting: &'a Pubkey) -> Self {
+        Self { greeting }
+    }
+
+    pub fn pda(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[PENDING_SET_SEED, self.greeting.as_ref()], program_id)
+    }
+
+    pub fn with_bump(&self, bump: &'a [u8; 1]) -> [&'a [u8]; 3] {
+        [PENDING_SET_SEED, self.greeting.as_ref(), bump]
+    }
+}
+
+/// The allowlist of `greeting` checked by AllowlistedIncrement
+pub struct AllowlistSeeds<'a> {
+    pub greeting: &'a Pubkey,
+}
+
+impl<'a> AllowlistSeeds<'a> {
+    pub fn new(greeting: &'a Pubkey) -> Self {
+        Self { greeting }
+    }
+
+    pub fn pda(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[ALLOWLIST_SEED, self.greeting.as_ref()], program_id)
+    }
+
+    pub fn with_bump(&self, bump: &'a [u8; 1]) -> [&'a [u8]; 3] {
+        [ALLOWLIST_SEED, self.greeting.as_ref(), bump]
+    }
+}
+
+/// The greeter statistics of `greeting` kept by SignedIncrement
+pub struct GreeterStatsSeeds<'a> {
+    pub greeting: &'a Pubkey,
+}
+
+impl<'a> GreeterStatsSeeds<'a> {
+    pub fn new(greeting: &'a Pubkey) -> Self {
+        Self { greeting }
+    }
+
+    pub fn pda(&self, program_id: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[GREETER_STATS_SEED, self.greeting.as_ref()], program_id)
+    }
+
+    pub fn with_bump(&self, bump: &'a [u8; 1]) -> [&'a [u8]; 3] {
+        [GREETER_STATS_SEED, self.greeting.as_ref(), bump]
+    }
+}
+
+/// The ProgramData account of `program_id` under the upgradeable loader.
+/// The loader owns it and derives it from the program id alone, so unlike
+/// the PDAs above there are no seeds to sign with.
+pub fn program_data_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // the seeds the program signs with have to derive the address the
+    // contexts check against
+    #[test]
+    fn test_signer_seeds_derive_pda() {
+        let program_id = Pubkey::new_unique();
+        let target = Pubkey::new_unique();
+        let voter = Pubkey::new_unique();
+
+        let receipt = ReceiptSeeds::new(&target, &voter);
+        let (address, bump) = receipt.pda(&program_id);
+        let bump = [bump];
+        assert_eq!(
+            Pubkey::create_program_address(&receipt.with_bump(&bump), &program_id),
+            Ok(address)
+        );
+
+        let attestation = AttestationSeeds::new(&target);
+        let (address, bump) = attestation.pda(&program_id);
+        let bump = [bump];
+        assert_eq!(
+            Pubkey::create_program_address(&attestation.with_bump(&bump), &program_id),
+            Ok(address)
+        );
+
+        let metadata = MetadataSeeds::new(&target);
+        let (address, bump) = metadata.pda(&program_id);
+        let bump = [bump];
+        assert_eq!(
+            Pubkey::create_program_address(&metadata.with_bump(&bump), &program_id),
+            Ok(address)
+        );
+        // same greeting, different kind of record, different address
+        assert_ne!(metadata.pda(&program_id).0, attestation.pda(&program_id).0);
+
+        let vault = VaultSeeds::new(&target, &voter);
+        let (address, bump) = vault.pda(&program_id);
+        let bump = [bump];
+        assert_eq!(
+            Pubkey::create_program_address(&vault.with_bump(&bump), &program_id),
+            Ok(address)
+        );
+        assert_ne!(address, receipt.pda(&program_id).0);
+
+        let badge = BadgeSeeds::new(&target, 10);
+        let (address, bump) = badge.pda(&program_id);
+        let bump = [bump];
+        assert_eq!(
+            Pubkey::create_program_address(&badge.with_bump(&bump), &program_id),
+            Ok(address)
+        );
+        assert_ne!(address, BadgeSeeds::new(&target, 100).pda(&program_id).0);
+
+        let streak = StreakSeeds::new(&target, &voter);
+        let (address, bump) = streak.pda(&program_id);
+        let bump = [bump];
+        assert_eq!(
+            Pubkey::create_program_address(&streak.with_bump(&bump), &program_id),
+            Ok(address)
+        );
+        assert_ne!(address, vault.pda(&program_id).0);
+
+        let pending = PendingSetSeeds::new(&target);
+        let (address, bump) = pending.pda(&program_id);
+        let bump = [bump];
+        assert_eq!(
+            Pubkey::create_program_address(&pending.with_bump(&bump), &program_id),
+            Ok(address)
+        );
+
+        assert_ne!(address, metadata.pda(&program_id).0);
+
+        let allowlist = AllowlistSeeds::new(&target);
+        let (address, bump) = allowlist.pda(&program_id);
+        let bump = [bump];
+        assert_eq!(
+            Pubkey::create_program_address(&allowlist.with_bump(&bump), &program_id),
+            Ok(address)
+        );
+        assert_ne!(address, pending.pda(&program_id).0);
+
+        let stats = GreeterStatsSeeds::new(&target);
+        let (address, bump) = stats.pda(&program_id);
+        let bump = [bump];
+        assert_eq!(
+            Pubkey::create_program_address(&stats.with_bump(&bump), &program_id),
+            Ok(address)
+        );
+        assert_ne!(address, allowlist.pda(&program_id).0);
+    }
+}
@@ -0,0 +1,703 @@
+//src/program-rust/types/src/state.rs
+// Account state owned by the hello world program
+//
+// GreetingAccount, Streak and GreeterStats can be built with hand written
+// Borsh impls instead of the derives, see layout.rs.
+
+use crate::layout::FixedLayout;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    entrypoint::ProgramResult, hash::hashv, msg, program_error::ProgramError, pubkey::Pubkey,
+};
+use std::alloc::{alloc_zeroed, handle_alloc_error, Layout};
+
+/// Define the type of state stored in accounts
+#[cfg_attr(
+    not(feature = "fixed-layout"),
+    derive(BorshSerialize, BorshDeserialize)
+)]
+#[derive(Debug)]
+pub struct GreetingAccount {
+    /// number of greetings
+    pub counter: u32,
+}
+
+impl GreetingAccount {
+    /// Size in bytes of a serialized GreetingAccount
+    pub const LEN: usize = 4;
+    /// Byte offset of the `counter` field inside the account data
+    pub const COUNTER_OFFSET: usize = 0;
+    /// Version of the field layout above, reported by GetVersion
+    pub const LAYOUT_VERSION: u8 = 1;
+}
+
+// Read the counter straight out of the account data without running the full
+// Borsh deserializer. The account length is checked first so that the fixed
+// offset is known to point at the counter bytes.
+pub fn read_counter(data: &[u8]) -> Result<u32, ProgramError> {
+    if data.len() != GreetingAccount::LEN {
+        msg!("Greeted account data has an unexpected length");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    let start = GreetingAccount::COUNTER_OFFSET;
+    let bytes = data
+        .get(start..start + 4)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    let mut counter = [0u8; 4];
+    counter.copy_from_slice(bytes);
+    Ok(u32::from_le_bytes(counter))
+}
+
+// Write the counter back into the same 4 bytes read by read_counter()
+pub fn write_counter(data: &mut [u8], counter: u32) -> ProgramResult {
+    let start = GreetingAccount::COUNTER_OFFSET;
+    let bytes = data
+        .get_mut(start..start + 4)
+        .ok_or(ProgramError::InvalidAccountData)?;
+    bytes.copy_from_slice(&counter.to_le_bytes());
+    Ok(())
+}
+
+/// Proof that `voter` has voted on `target`, stored at
+/// ReceiptSeeds::new(target, voter).pda(). Its existence is the dedup
+/// check, the fields are there so the receipt can be read back on its own.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct VoteReceipt {
+    /// The account that was voted on
+    pub target: Pubkey,
+    /// The wallet that voted
+    pub voter: Pubkey,
+    /// What was voted for, VoteReceipt::UP or VoteReceipt::DOWN
+    pub choice: u8,
+}
+
+impl VoteReceipt {
+    /// Size in bytes of a serialized VoteReceipt
+    pub const LEN: usize = 32 + 32 + 1;
+    /// `choice` of an Upvote
+    pub const UP: u8 = 0;
+    /// `choice` of a Downvote
+    pub const DOWN: u8 = 1;
+}
+
+/// The latest content hash attested on `greeting`, stored at
+/// AttestationSeeds::new(greeting).pda()
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct Attestation {
+    /// The greeting account the record belongs to
+    pub greeting: Pubkey,
+    /// Hash of the off-chain document, the program doesn't interpret it
+    pub hash: [u8; 32],
+    /// Slot of the Attest that wrote this record
+    pub slot: u64,
+    /// The wallet that signed the Attest
+    pub attester: Pubkey,
+}
+
+impl Attestation {
+    /// Size in bytes of a serialized Attestation
+    pub const LEN: usize = 32 + 32 + 8 + 32;
+}
+
+/// Number of op_ids a RecentOps window remembers
+pub const RECENT_OPS_LEN: usize = 16;
+
+/// The op_ids of the last RECENT_OPS_LEN Idempotent operations on
+/// `greeting`, stored at RecentOpsSeeds::new(greeting).pda(). A retried
+/// operation whose op_id is still in the window is rejected.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct RecentOps {
+    /// The greeting account the window belongs to
+    pub greeting: Pubkey,
+    /// Slot in `op_ids` the next op_id is written to, wrapping around
+    pub next: u8,
+    /// Recorded op_ids, all zero for slots not used yet
+    pub op_ids: [[u8; 16]; RECENT_OPS_LEN],
+}
+
+impl RecentOps {
+    /// Size in bytes of a serialized RecentOps
+    pub const LEN: usize = 32 + 1 + 16 * RECENT_OPS_LEN;
+
+    pub fn new(greeting: Pubkey) -> Self {
+        Self {
+            greeting,
+            next: 0,
+            op_ids: [[0; 16]; RECENT_OPS_LEN],
+        }
+    }
+
+    pub fn contains(&self, op_id: &[u8; 16]) -> bool {
+        self.op_ids.contains(op_id)
+    }
+
+    /// Record `op_id`, evicting the oldest one once the window is full
+    pub fn push(&mut self, op_id: [u8; 16]) {
+        let next = self.next as usize % RECENT_OPS_LEN;
+        self.op_ids[next] = op_id;
+        self.next = ((next + 1) % RECENT_OPS_LEN) as u8;
+    }
+}
+
+/// Lamports `authority` locked against the counter of `greeting`, stored
+/// at VaultSeeds::new(greeting, authority).pda(). The vault account's
+/// balance above its rent exemption is the amount deposited.
+///
+/// The vault only looks at the counter, and anyone can move it by other
+/// means than Increment: Set and CopyFrom write any value while the counter
+/// is at most SET_CONFIRM_THRESHOLD, ProposeSet and ConfirmSet above it.
+/// A target is a goal, not a lock against the depositor or anyone else.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct Vault {
+    /// The greeting account whose counter unlocks the vault
+    pub greeting: Pubkey,
+    /// Who deposits, and withdraws once unlocked
+    pub authority: Pubkey,
+    /// Counter value from which Withdraw is allowed, set by the first
+    /// Deposit
+    pub target: u32,
+}
+
+impl Vault {
+    /// Size in bytes of a serialized Vault
+    pub const LEN: usize = 32 + 32 + 4;
+    /// Byte offset of the `authority` field inside the account data
+    pub const AUTHORITY_OFFSET: usize = 32;
+}
+
+/// Counter values a greeting can claim a Badge for
+pub const MILESTONES: [u32; 3] = [10, 100, 1000];
+
+/// Proof that the counter of `greeting` reached `milestone`, stored at
+/// BadgeSeeds::new(greeting, milestone).pda(). Frontends can list the
+/// badges of a greeting with getProgramAccounts, filtering on a data size
+/// of Badge::LEN and on the greeting key at offset 0.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct Badge {
+    /// The greeting account that reached the milestone
+    pub greeting: Pubkey,
+    /// One of MILESTONES
+    pub milestone: u32,
+    /// Slot of the ClaimBadge that created the badge
+    pub slot: u64,
+}
+
+impl Badge {
+    /// Size in bytes of a serialized Badge
+    pub const LEN: usize = 32 + 4 + 8;
+}
+
+/// Check-in streak of `user` on `greeting`, stored at
+/// StreakSeeds::new(greeting, user).pda(). A streak counts consecutive
+/// epochs with at least one CheckIn.
+#[cfg_attr(
+    not(feature = "fixed-layout"),
+    derive(BorshSerialize, BorshDeserialize)
+)]
+#[derive(Debug, PartialEq)]
+pub struct Streak {
+    /// The greeting account checked in on
+    pub greeting: Pubkey,
+    /// The wallet checking in
+    pub user: Pubkey,
+    /// Epoch of the latest check-in, meaningless while current_streak is 0
+    pub last_epoch: u64,
+    /// Consecutive epochs with a check-in, up to and including last_epoch
+    pub current_streak: u32,
+    /// Longest streak so far
+    pub best_streak: u32,
+}
+
+impl Streak {
+    /// Size in bytes of a serialized Streak
+    pub const LEN: usize = 32 + 32 + 8 + 4 + 4;
+
+    /// A streak with no check-ins yet
+    pub fn new(greeting: Pubkey, user: Pubkey) -> Self {
+        Self {
+            greeting,
+            user,
+            last_epoch: 0,
+            current_streak: 0,
+            best_streak: 0,
+        }
+    }
+
+    /// Record a check-in in `epoch`. A second check-in in the same epoch
+    /// changes nothing, one in the next epoch extends the streak and any
+    /// later one starts a new streak of 1.
+    pub fn check_in(&mut self, epoch: u64) {
+        if self.current_streak > 0 && epoch == self.last_epoch {
+            return;
+        }
+        let consecutive = self.last_epoch.checked_add(1) == Some(epoch);
+        self.current_streak = if self.current_streak > 0 && consecutive {
+            self.current_streak.saturating_add(1)
+        } else {
+            1
+        };
+        self.best_streak = self.best_streak.max(self.current_streak);
+        self.last_epoch = epoch;
+    }
+}
+
+/// Counter value above which Set is refused and the new value has to be
+/// proposed with ProposeSet and confirmed with ConfirmSet
+pub const SET_CONFIRM_THRESHOLD: u32 = 1_000;
+
+/// A Set of `greeting` proposed and not confirmed yet, stored at
+/// PendingSetSeeds::new(greeting).pda(). A new ProposeSet from the same
+/// proposer replaces it, ConfirmSet applies it and closes the account and
+/// CancelSet closes it without applying it.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct PendingSet {
+    /// The greeting account to set
+    pub greeting: Pubkey,
+    /// Who proposed it and paid for the account, refunded on ConfirmSet
+    /// or CancelSet
+    pub proposer: Pubkey,
+    /// The value to set the counter to
+    pub value: u32,
+    /// Slot of the ProposeSet, ConfirmSet is allowed config::SET_CONFIRM_DELAY
+    /// slots later
+    pub slot: u64,
+}
+
+impl PendingSet {
+    /// Size in bytes of a serialized PendingSet
+    pub const LEN: usize = 32 + 32 + 4 + 8;
+}
+
+/// Merkle root of the wallets allowed to AllowlistedIncrement `greeting`,
+/// stored at AllowlistSeeds::new(greeting).pda(). The tree is built with
+/// merkle::root() and only the root is kept on-chain, whatever the number
+/// of members.
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct Allowlist {
+    /// The greeting account the allowlist belongs to
+    pub greeting: Pubkey,
+    /// Who may replace the root: the signer of the SetAllowlist that
+    /// created the account
+    pub authority: Pubkey,
+    /// Root of the tree over the members' merkle::leaf()
+    pub root: [u8; 32],
+}
+
+impl Allowlist {
+    /// Size in bytes of a serialized Allowlist
+    pub const LEN: usize = 32 + 32 + 32;
+    /// Byte offset of the `authority` field inside the account data
+    pub const AUTHORITY_OFFSET: usize = 32;
+}
+
+/// Size in bytes of the bloom filter in GreeterStats
+pub const BLOOM_BYTES: usize = 1024;
+/// Bits set in the bloom filter per greeter
+pub const BLOOM_HASHES: usize = 4;
+/// Registers of the HyperLogLog sketch in GreeterStats, a power of two
+pub const HLL_REGISTERS: usize = 512;
+// Ranks are capped here so 2^(HLL_MAX_RANK - rank) always fits in the sum
+// of the registers. Going past it takes around 2^50 greeters.
+const HLL_MAX_RANK: u8 = 50;
+// Fractional bits of GreeterStats::hll_estimate
+const HLL_FRACTION_BITS: u32 = 16;
+
+/// Greeters seen by SignedIncrement on `greeting`, stored at
+/// GreeterStatsSeeds::new(greeting).pda()
+///
+/// The bloom filter can claim a new greeter was already seen, never the
+/// other way around, so unique_greeters_estimate only ever undercounts.
+/// With the sizes above that stays under 3% at a thousand greeters and
+/// gets steadily worse past that.
+///
+/// The HyperLogLog sketch keeps working at any size, with a relative
+/// error around 4% (0.83 / sqrt(HLL_REGISTERS)). Its estimate is kept up
+/// to date as registers change (the HIP estimator), so reading it costs
+/// nothing and needs no floating point.
+#[cfg_attr(
+    not(feature = "fixed-layout"),
+    derive(BorshSerialize, BorshDeserialize)
+)]
+#[derive(Debug, PartialEq)]
+pub struct GreeterStats {
+    /// The greeting account the statistics belong to
+    pub greeting: Pubkey,
+    /// Greeters the bloom filter took to be new
+    pub unique_greeters_estimate: u32,
+    /// BLOOM_BYTES * 8 bits, BLOOM_HASHES of them set for each greeter
+    pub bloom: [u8; BLOOM_BYTES],
+    /// Highest rank seen in each register of the sketch
+    pub hll: [u8; HLL_REGISTERS],
+    /// Estimated unique greeters from the sketch, in 1/65536ths, see
+    /// unique_greeters_hll()
+    pub hll_estimate: u64,
+}
+
+impl GreeterStats {
+    /// Size in bytes of a serialized GreeterStats
+    pub const LEN: usize = 32 + 4 + BLOOM_BYTES + HLL_REGISTERS + 8;
+
+    /// Statistics with no greeters yet
+    pub fn new(greeting: Pubkey) -> Self {
+        Self {
+            greeting,
+            unique_greeters_estimate: 0,
+            bloom: [0; BLOOM_BYTES],
+            hll: [0; HLL_REGISTERS],
+            hll_estimate: 0,
+        }
+    }
+
+    /// Statistics with no greeters yet, on the heap. See unpack_boxed().
+    pub fn new_boxed(greeting: Pubkey) -> Box<Self> {
+        let mut stats = Self::zeroed_boxed();
+        stats.greeting = greeting;
+        stats
+    }
+
+    /// Decode statistics onto the heap. At LEN bytes a GreeterStats is a
+    /// large part of the 4KB stack frame a function gets on chain, so
+    /// handlers only hold it boxed. It is decoded in place, field by field,
+    /// and never passes through the stack.
+    pub fn unpack_boxed(data: &[u8]) -> Result<Box<Self>, ProgramError> {
+        if data.len() != Self::LEN {
+            msg!("GreeterStats account data has an unexpected length");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut stats = Self::zeroed_boxed();
+        stats.read_into(data);
+        Ok(stats)
+    }
+
+    /// Encode into `data`, which must be LEN bytes long, without the stack
+    /// buffer the `fixed-layout` Borsh impl goes through
+    pub fn pack(&self, data: &mut [u8]) -> ProgramResult {
+        if data.len() != Self::LEN {
+            msg!("GreeterStats account data has an unexpected length");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        self.write(data);
+        Ok(())
+    }
+
+    // Box::new() builds its value on the stack before moving it, so the
+    // allocation is made directly. All zero bytes are a valid GreeterStats,
+    // every field being an integer, a byte array or a Pubkey.
+    fn zeroed_boxed() -> Box<Self> {
+        let layout = Layout::new::<Self>();
+        // SAFETY: the layout is that of Self, which isn't zero sized, and
+        // the zeroed memory is a valid Self as above
+        unsafe {
+            let stats = alloc_zeroed(layout) as *mut Self;
+            if stats.is_null() {
+                handle_alloc_error(layout);
+            }
+            Box::from_raw(stats)
+        }
+    }
+
+    /// Unique greeters estimated by the HyperLogLog sketch, rounded
+    pub fn unique_greeters_hll(&self) -> u64 {
+        (self.hll_estimate + (1 << (HLL_FRACTION_BITS - 1))) >> HLL_FRACTION_BITS
+    }
+
+    /// Add `greeter` to the filter and the sketch. Returns whether the
+    /// filter took it to be new, in which case unique_greeters_estimate was
+    /// incremented.
+    pub fn record(&mut self, greeter: &Pubkey) -> bool {
+        self.record_hll(greeter);
+        self.record_bloom(greeter)
+    }
+
+    fn record_bloom(&mut self, greeter: &Pubkey) -> bool {
+        let hash = hashv(&[GREETER_STATS_BLOOM, greeter.as_ref()]).to_bytes();
+        let mut new = false;
+        for chunk in hash.chunks(4).take(BLOOM_HASHES) {
+            let mut index = [0; 4];
+            index.copy_from_slice(chunk);
+            let bit = u32::from_le_bytes(index) as usize % (BLOOM_BYTES * 8);
+            let mask = 1 << (bit % 8);
+            new |= self.bloom[bit / 8] & mask == 0;
+            self.bloom[bit / 8] |= mask;
+        }
+        if new {
+            self.unique_greeters_estimate = self.unique_greeters_estimate.saturating_add(1);
+        }
+        new
+    }
+
+    // The register is picked by the low bits of the hash and the rank is
+    // one more than the leading zeros of the next 8 bytes. When a greeter
+    // raises a register, it was new with the probability q of any greeter
+    // raising one, so it adds 1 / q to the estimate.
+    fn record_hll(&mut self, greeter: &Pubkey) {
+        let hash = hashv(&[GREETER_STATS_HLL, greeter.as_ref()]).to_bytes();
+        let register = u16::from_le_bytes([hash[0], hash[1]]) as usize % HLL_REGISTERS;
+        let mut word = [0; 8];
+        word.copy_from_slice(&hash[2..10]);
+        let rank = (u64::from_le_bytes(word).leading_zeros() as u8 + 1).min(HLL_MAX_RANK);
+        if rank <= self.hll[register] {
+            return;
+        }
+
+        // q * HLL_REGISTERS * 2^HLL_MAX_RANK
+        let sum: u128 = self
+            .hll
+            .iter()
+            .map(|&rank| 1u128 << (HLL_MAX_RANK - rank))
+            .sum();
+        let scale = (HLL_REGISTERS as u128) << (HLL_MAX_RANK as u32 + HLL_FRACTION_BITS);
+        let added = (scale / sum) as u64;
+        self.hll_estimate = self.hll_estimate.saturating_add(added);
+        self.hll[register] = rank;
+    }
+}
+
+// keep the bloom and sketch positions independent of each other and of
+// any other hash of the key
+const GREETER_STATS_BLOOM: &[u8] = b"bloom";
+const GREETER_STATS_HLL: &[u8] = b"hll";
+
+/// Longest metadata URI, in bytes
+pub const MAX_URI_LEN: usize = 200;
+
+/// Off-chain metadata of `greeting`, stored at
+/// MetadataSeeds::new(greeting).pda()
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct GreetingMetadata {
+    /// The greeting account the record belongs to
+    pub greeting: Pubkey,
+    /// Who may change the record: the signer of the SetUri or SetLanguage
+    /// that created it
+    pub authority: Pubkey,
+    /// ISO 639-1 code of the language the counter is logged in, one of
+    /// locale::SUPPORTED
+    pub language: [u8; 2],
+    /// Link to a JSON document (avatar, description) for explorers and
+    /// frontends to render
+    pub uri: String,
+}
+
+impl GreetingMetadata {
+    /// Size in bytes of the record with the longest URI, which is the
+    /// space allocated for it
+    pub const LEN: usize = 32 + 32 + 2 + 4 + MAX_URI_LEN;
+    /// Byte offset of the `authority` field inside the account data
+    pub const AUTHORITY_OFFSET: usize = 32;
+
+    /// Decode the record from the start of `data`. A shorter URI leaves
+    /// unused bytes at the end, which are ignored.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        Self::deserialize(&mut &*data).map_err(|_| ProgramError::InvalidAccountData)
+    }
+}
+
+/// Most options a Poll can have
+pub const MAX_POLL_OPTIONS: usize = 8;
+/// Longest label, in bytes, a poll option can have
+pub const MAX_POLL_LABEL_LEN: usize = 32;
+
+/// A poll with up to MAX_POLL_OPTIONS labeled options, each counted
+/// separately
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct Poll {
+    /// Set by CreatePoll, a zeroed account is an unused poll
+    pub is_initialized: bool,
+    /// Who may close the poll
+    pub authority: Pubkey,
+    /// Whether Vote is still accepted
+    pub is_open: bool,
+    pub options: Vec<PollOption>,
+}
+
+/// One option of a Poll
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct PollOption {
+    pub label: String,
+    /// number of votes for this option
+    pub count: u32,
+}
+
+impl Poll {
+    /// Size in bytes of the largest Poll, which is the space clients
+    /// allocate for a poll account
+    pub const LEN: usize = 1 + 32 + 1 + 4 + MAX_POLL_OPTIONS * (4 + MAX_POLL_LABEL_LEN + 4);
+
+    /// Decode a poll from the start of `data`. Polls are smaller than
+    /// the account until all options have the longest label, so the
+    /// unused bytes after the poll are ignored.
+    pub fn unpack(data: &[u8]) -> Result<Self, ProgramError> {
+        Self::deserialize(&mut &*data).map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Same as unpack(), failing if CreatePoll hasn't run yet
+    pub fn unpack_initialized(data: &[u8]) -> Result<Self, ProgramError> {
+        let poll = Self::unpack(data)?;
+        if !poll.is_initialized {
+            return Err(ProgramError::UninitializedAccount);
+        }
+        Ok(poll)
+    }
+}
+
+/// Response of HelloInstruction::GetVersion, returned via return data
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub struct VersionInfo {
+    /// Layout version of GreetingAccount, bumped whenever fields change
+    pub layout_version: u8,
+    /// `version` from the program's Cargo.toml
+    pub version: String,
+}
+
+impl VersionInfo {
+    /// Version info of a program build, `version` being its package
+    /// version. The layout version is the one this crate defines.
+    pub fn new(version: &str) -> Self {
+        Self {
+            layout_version: GreetingAccount::LAYOUT_VERSION,
+            version: version.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_streak_check_in() {
+        let mut streak = Streak::new(Pubkey::new_unique(), Pubkey::new_unique());
+
+        // the first check-in starts a streak, even in epoch 0
+        streak.check_in(0);
+        assert_eq!((streak.current_streak, streak.best_streak), (1, 1));
+        // same epoch again: no change
+        streak.check_in(0);
+        assert_eq!((streak.current_streak, streak.best_streak), (1, 1));
+        streak.check_in(1);
+        streak.check_in(2);
+        assert_eq!((streak.current_streak, streak.best_streak), (3, 3));
+
+        // one epoch skipped resets the streak, the best is kept
+        streak.check_in(4);
+        assert_eq!((streak.current_streak, streak.best_streak), (1, 3));
+        assert_eq!(streak.last_epoch, 4);
+        streak.check_in(5);
+        assert_eq!((streak.current_streak, streak.best_streak), (2, 3));
+
+        // an epoch earlier than the last one can't extend it either
+        streak.check_in(3);
+        assert_eq!((streak.current_streak, streak.best_streak), (1, 3));
+
+        // no overflow at the ends of either counter
+        streak.last_epoch = u64::MAX - 1;
+        streak.current_streak = u32::MAX;
+        streak.check_in(u64::MAX);
+        assert_eq!(streak.current_streak, u32::MAX);
+        assert_eq!(streak.best_streak, u32::MAX);
+        streak.check_in(0);
+        assert_eq!((streak.current_streak, streak.best_streak), (1, u32::MAX));
+    }
+
+    #[test]
+    fn test_greeter_stats_bloom() {
+        let mut stats = GreeterStats::new(Pubkey::new_unique());
+        let greeters: Vec<Pubkey> = (0..1000).map(|_| Pubkey::new_unique()).collect();
+        let looked_new = greeters.iter().filter(|g| stats.record(g)).count();
+        assert_eq!(stats.unique_greeters_estimate as usize, looked_new);
+        // a few false positives, within the 3% documented on GreeterStats
+        assert!(looked_new >= 970, "{}", looked_new);
+
+        // nobody already recorded looks new again
+        for greeter in &greeters {
+            assert!(!stats.record(greeter));
+        }
+        assert_eq!(stats.unique_greeters_estimate as usize, looked_new);
+        assert_eq!(stats.try_to_vec().unwrap().len(), GreeterStats::LEN);
+    }
+
+    #[test]
+    fn test_greeter_stats_boxed() {
+        let greeting = Pubkey::new_unique();
+        assert_eq!(
+            *GreeterStats::new_boxed(greeting),
+            GreeterStats::new(greeting)
+        );
+
+        let mut stats = GreeterStats::new_boxed(greeting);
+        for _ in 0..50 {
+            stats.record(&Pubkey::new_unique());
+        }
+        let mut data = vec![0; GreeterStats::LEN];
+        stats.pack(&mut data).unwrap();
+        assert_eq!(data, stats.try_to_vec().unwrap());
+        assert_eq!(GreeterStats::unpack_boxed(&data).unwrap(), stats);
+
+        // the length is checked like try_from_slice() does
+        assert!(GreeterStats::unpack_boxed(&data[1..]).is_err());
+        data.push(0);
+        assert!(GreeterStats::unpack_boxed(&data).is_err());
+        assert!(stats.pack(&mut data).is_err());
+    }
+
+    #[test]
+    fn test_greeter_stats_hll_error() {
+        // fixed keys so the result doesn't change between runs
+        let greeter = |i: u32| Pubkey::new_from_array(hashv(&[&i.to_le_bytes()]).to_bytes());
+        let mut stats = GreeterStats::new(Pubkey::new_unique());
+        assert_eq!(stats.unique_greeters_hll(), 0);
+
+        let mut greeted = 0;
+        for &unique in &[1, 10, 100, 1_000, 10_000, 100_000] {
+            while greeted < unique {
+                stats.record(&greeter(greeted));
+                greeted += 1;
+            }
+            // 3 standard errors, about 11%, plus one for the smallest counts
+            let estimate = stats.unique_greeters_hll() as f64;
+            let allowed = 0.11 * unique as f64 + 1.0;
+            assert!(
+                (estimate - unique as f64).abs() <= allowed,
+                "estimated {} for {}",
+                estimate,
+                unique
+            );
+        }
+
+        // greeting again changes nothing
+        let before = stats.hll_estimate;
+        for i in 0..1_000 {
+            stats.record(&greeter(i));
+        }
+        assert_eq!(stats.hll_estimate, before);
+    }
+
+    proptest! {
+        #[test]
+        fn test_greeting_round_trip(counter in any::<u32>()) {
+            let bytes = GreetingAccount { counter }.try_to_vec().unwrap();
+            prop_assert_eq!(bytes.len(), GreetingAccount::LEN);
+            prop_assert_eq!(GreetingAccount::try_from_slice(&bytes).unwrap().counter, counter);
+        }
+
+        // the fast path has to agree with Borsh byte for byte
+        #[test]
+        fn test_fast_path_matches_borsh(counter in any::<u32>()) {
+            let bytes = GreetingAccount { counter }.try_to_vec().unwrap();
+            prop_assert_eq!(read_counter(&bytes).unwrap(), counter);
+
+            let mut written = vec![0; GreetingAccount::LEN];
+            write_counter(&mut written, counter).unwrap();
+            prop_assert_eq!(written, bytes);
+        }
+
+        #[test]
+        fn test_read_counter_rejects_other_lengths(
+            data in prop::collection::vec(any::<u8>(), 0..64)
+                .prop_filter("not LEN", |data| data.len() != GreetingAccount::LEN)
+        ) {
+            prop_assert_eq!(read_counter(&data), Err(ProgramError::InvalidAccountData));
+        }
+    }
+}